@@ -0,0 +1,81 @@
+//! Parses 10k tile lines and prints how long that takes.
+//! The first line pays for compiling the regexes, the rest shouldn't, so their average should sit far below it.
+//! With `parallel` it also compares the sequential and parallel init parsers on testfiles/mass_deser.txt.
+//! Run with `cargo bench --bench parse`, or `cargo bench --features parallel --bench parse`.
+
+use std::time::{Duration, Instant};
+
+use rw_tileman::lingo_de;
+
+const LINES: usize = 10_000;
+const TILES_PER_CATEGORY: usize = 100;
+#[cfg(feature = "parallel")]
+const RUNS: u32 = 10;
+
+fn tile_line(index: usize) -> String {
+    format!(
+        r#"[#nm:"tile {index}", #sz:point(2,2), #specs:[1, 1, 0, 1], #specs2:0, #tp:"voxelStruct", #repeatL:[1, 8, 1], #bfTiles:1, #rnd:{rnd}, #ptPos:0, #tags:["notTrashProp", "nonSolid"]]"#,
+        rnd = index % 4 + 1
+    )
+}
+
+fn per_line(total: Duration) -> Duration {
+    total / LINES as u32
+}
+
+fn main() {
+    let lines: Vec<String> = (0..LINES).map(tile_line).collect();
+
+    let start = Instant::now();
+    lingo_de::parse_tile_info(&lines[0], true).expect("bench line should parse");
+    let first = start.elapsed();
+
+    let start = Instant::now();
+    for line in lines.iter() {
+        lingo_de::parse_tile_info(line, true).expect("bench line should parse");
+    }
+    let tiles = start.elapsed();
+
+    let text = lines
+        .chunks(TILES_PER_CATEGORY)
+        .enumerate()
+        .map(|(index, chunk)| format!("-[\"category {index}\", color(0,0,0)]\n{}", chunk.join("\n")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let start = Instant::now();
+    let init = lingo_de::parse_tile_init(text, Vec::new(), Default::default())
+        .expect("bench init should parse");
+    let whole_init = start.elapsed();
+    assert_eq!(init.iter_tiles().count(), LINES);
+
+    println!("first line (compiles regexes): {first:?}");
+    println!("parse_tile_info, {LINES} lines: {tiles:?} ({:?} per line)", per_line(tiles));
+    println!("parse_tile_init, {LINES} lines: {whole_init:?} ({:?} per line)", per_line(whole_init));
+
+    #[cfg(feature = "parallel")]
+    sequential_against_parallel();
+}
+
+/// Average of [`RUNS`] parses of the same init each way
+#[cfg(feature = "parallel")]
+fn sequential_against_parallel() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testfiles");
+    let text = std::fs::read_to_string(root.join("mass_deser.txt")).expect("could not read mass_deser.txt");
+    let options = lingo_de::ParseOptions::default();
+    let average = |parse: &dyn Fn() -> rw_tileman::TileInit| {
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            parse();
+        }
+        start.elapsed() / RUNS
+    };
+    let sequential = average(&|| {
+        lingo_de::parse_tile_init_with(text.clone(), Vec::new(), root.clone(), &options)
+            .expect("mass_deser.txt should parse")
+    });
+    let parallel = average(&|| {
+        lingo_de::parse_tile_init_parallel(text.clone(), Vec::new(), root.clone(), &options)
+            .expect("mass_deser.txt should parse")
+    });
+    println!("mass_deser.txt: sequential {sequential:?}, parallel {parallel:?}");
+}
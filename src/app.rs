@@ -1,7 +1,9 @@
+use std::collections::{HashSet, VecDeque};
+
 use log;
 
 use crate::{
-    lingo_de::{self, DeserError},
+    lingo_de,
     lingo_ser,
     utl::*,
     *
@@ -10,116 +12,471 @@ use crate::{
 pub enum AppScheduledAction {
     None,
     Reload,
+    /// the inits were just written, what's on disk is now the loaded pack
+    Saved,
     MoveCategory(usize, i32),
+    PreviewChanges,
     DisplayMessage {
         icon: msgbox::IconType,
         title: String,
         text: String,
     }
 }
-#[derive(Debug)]
-pub enum AppError {
-    TextureNotLargeEnough(String),
-    InvalidTexture(String, png::DecodingError),
-    MissingTexture(String),
-    IOError(String),
-    Todo,
+impl AppScheduledAction {
+    /// Whether running the action changes the loaded pack, those are dropped in read only mode
+    pub fn edits_pack(&self) -> bool {
+        matches!(self, AppScheduledAction::MoveCategory(..))
+    }
 }
+pub use crate::AppError;
+/// Parsed init and the main init text as read from disk
+type LoadedData = (TileInit, String);
+
+pub const RECENT_PATHS_CAP: usize = 10;
+const STORAGE_RECENT_PATHS_KEY: &str = "recent_paths";
+
 pub struct TilemanApp {
     path_selection: String, //necessary duplicate because egui wants unicode strings
-    search_selection: String,
+    recent_paths: VecDeque<String>, //newest first
+    tile_filter: TileFilter,
+    read_only: bool, //inspect mode, nothing can be edited or saved
     selected_tile: Option<(usize, usize)>,
     selected_tile_cache: Option<(usize, usize)>,
-    preview_cache: Option<PreviewCache>,
+    selected_index: Option<usize>, //keyboard cursor, a position in TileFilter::visible_tiles
+    preview_cache: LruCache<(usize, usize), PreviewCache>,
     preview_scale: f32,
     init: Option<TileInit>,
+    load_error: Option<String>, //shown in the top bar, editing stays locked until a load works
+    merged_files: usize, //more than one when the path field listed several inits, those can't be saved back
+    loading: Option<(lingo_de::BackgroundLoad, std::path::PathBuf)>, //running load and the root it reads
+    original_main_init: Option<String>, //main init.txt as it was on load, for previewing changes
+    changes_preview: Option<Vec<DiffLine>>,
     scheduled_action: AppScheduledAction,
     config: AppPersistentConfig,
-    pub lhandle: flexi_logger::LoggerHandle,
+    #[cfg(feature = "watch")]
+    watcher: Option<crate::watch::PackWatcher>,
+    #[cfg(feature = "watch")]
+    watched_root: Option<std::path::PathBuf>, //set even when watching it failed, so that isn't retried every frame
+    #[cfg(feature = "watch")]
+    loaded_main_init: Option<String>, //serialized right after loading, to tell whether there are unsaved edits
+    #[cfg(feature = "watch")]
+    changed_on_disk: bool, //files changed but reloading would throw away edits
+    pub lhandle: Option<flexi_logger::LoggerHandle>, //None when whoever made the app looks after logging
+}
+
+/// What the tile list shows
+#[derive(Debug, Clone)]
+pub(crate) struct TileFilter {
+    pub sort_key: SortKey, //only changes the order things are listed in, the pack keeps its order
+    pub search: String,
+    pub search_tags: bool, //also match the search against tags
+    pub fuzzy: bool, //search matches letters in order with gaps allowed, best matches first
+    pub hide_inactive: bool, //greyed out otherwise
+}
+
+impl Default for TileFilter {
+    fn default() -> Self {
+        Self {
+            sort_key: SortKey::Index,
+            search: String::new(),
+            search_tags: true,
+            fuzzy: false,
+            hide_inactive: false,
+        }
+    }
+}
+
+impl TileFilter {
+    pub(crate) fn matches(&self, item: &TileInfo) -> bool {
+        if self.hide_inactive && !item.active {
+            return false;
+        }
+        if self.search.is_empty() {
+            return true;
+        }
+        if self.fuzzy {
+            return self.fuzzy_score(item).is_some();
+        }
+        name_matches_search(&item.name, &self.search)
+            || (self.search_tags
+                && item
+                    .tags
+                    .iter()
+                    .any(|tag| name_matches_search(tag, &self.search)))
+    }
+    /// Best fuzzy score of the name and, with `search_tags`, the tags
+    pub(crate) fn fuzzy_score(&self, item: &TileInfo) -> Option<i64> {
+        let tag_scores = item
+            .tags
+            .iter()
+            .filter(|_| self.search_tags)
+            .filter_map(|tag| fuzzy_score(&self.search, tag));
+        fuzzy_score(&self.search, &item.name).into_iter().chain(tag_scores).max()
+    }
+    /// Positions in `init.categories` in the order the tile list shows them
+    pub(crate) fn category_order(&self, init: &TileInit) -> Vec<usize> {
+        let mut category_order: Vec<usize> = indices(&init.categories).collect();
+        if self.sort_key == SortKey::Name {
+            category_order.sort_by(|index1, index2| {
+                compare_names(&init.categories[*index1].name, &init.categories[*index2].name)
+            });
+        }
+        category_order
+    }
+    /// Positions in `category.tiles` of the matching tiles, in the order the tile list shows them
+    pub(crate) fn tile_order(&self, category: &crate::TileCategory) -> Vec<usize> {
+        let mut tile_order: Vec<usize> = indices(&category.tiles)
+            .filter(|index| self.matches(&category.tiles[*index]))
+            .collect();
+        if self.sort_key == SortKey::Name {
+            tile_order.sort_by(|index1, index2| {
+                compare_names(&category.tiles[*index1].name, &category.tiles[*index2].name)
+            });
+        }
+        if self.fuzzy && !self.search.is_empty() {
+            tile_order.sort_by_cached_key(|index| {
+                std::cmp::Reverse(self.fuzzy_score(&category.tiles[*index]))
+            });
+        }
+        tile_order
+    }
+    /// `(category, tile)` of every tile the list shows, top to bottom across categories, for keyboard navigation
+    pub(crate) fn visible_tiles(&self, init: &TileInit) -> Vec<(usize, usize)> {
+        self.category_order(init)
+            .into_iter()
+            .flat_map(|category_index| {
+                self.tile_order(&init.categories[category_index])
+                    .into_iter()
+                    .map(move |item_index| (category_index, item_index))
+            })
+            .collect()
+    }
+    /// Names of categories that have at least one matching tile, None when there is no search and all categories show
+    pub(crate) fn visible_categories(&self, init: &TileInit) -> Option<HashSet<String>> {
+        if self.search.is_empty() {
+            return None;
+        }
+        Some(
+            init.iter_tiles()
+                .filter(|(_, tile)| self.matches(tile))
+                .map(|(category, _)| category.name.clone())
+                .collect(),
+        )
+    }
 }
 
 #[derive(Clone)]
 pub struct PreviewCache {
     specs: egui::TextureHandle,
     specs2: Option<egui::TextureHandle>,
+    thumbnail: egui::TextureHandle,
 }
 
 impl TilemanApp {
     pub fn new(
-        _cc: &eframe::CreationContext,
+        cc: &eframe::CreationContext,
         config: AppPersistentConfig,
         lhandle: flexi_logger::LoggerHandle
     ) -> Result<Self, AppError> {
+        let root = config.root_path.clone();
+        let mut tileman_app = Self::from_config(cc.storage, config, Some(lhandle));
+        tileman_app.reload_from(root);
+        Ok(tileman_app)
+    }
+    /// Loads `path` right away instead of in the background, a pack that can't be loaded is an error
+    /// instead of a message in the top bar. Logging is left to the caller.
+    pub fn with_path(
+        cc: &eframe::CreationContext,
+        path: impl Into<std::path::PathBuf>,
+    ) -> Result<Self, AppError> {
+        Self::with_path_and_storage(cc.storage, path)
+    }
+    /// [`with_path`](Self::with_path) without a window, recent paths come from `storage` when there is one
+    pub fn with_path_and_storage(
+        storage: Option<&dyn eframe::Storage>,
+        path: impl Into<std::path::PathBuf>,
+    ) -> Result<Self, AppError> {
+        let root: std::path::PathBuf = path.into();
+        let config = AppPersistentConfig {
+            root_path: root.clone(),
+            output_path: root.clone(),
+            preview_cache_size: crate::DEFAULT_PREVIEW_CACHE_SIZE,
+        };
+        let mut tileman_app = Self::from_config(storage, config, None);
+        let loaded = lingo_de::load_tile_init(root.clone()).and_then(Self::with_main_init_text)?;
+        tileman_app.apply_loaded_data(Ok(loaded));
+        push_recent_path(&mut tileman_app.recent_paths, root.to_string_lossy().into_owned());
+        Ok(tileman_app)
+    }
+    /// Everything but the pack, which is left unloaded
+    fn from_config(
+        storage: Option<&dyn eframe::Storage>,
+        config: AppPersistentConfig,
+        lhandle: Option<flexi_logger::LoggerHandle>,
+    ) -> Self {
         let init = None;
-        let maybe_init = Self::load_data(config.root_path.clone());
-        
-        let mut tileman_app = Self {
+        let recent_paths = storage
+            .and_then(|storage| eframe::get_value(storage, STORAGE_RECENT_PATHS_KEY))
+            .unwrap_or_default();
+
+        Self {
             selected_tile: Default::default(),
             selected_tile_cache: None,
+            selected_index: None,
             init,
-            preview_cache: None,
+            load_error: None,
+            merged_files: 0,
+            loading: None,
+            original_main_init: None,
+            changes_preview: None,
+            preview_cache: LruCache::new(config.preview_cache_size),
             path_selection: config.root_path.to_string_lossy().into_owned(),
+            recent_paths,
             preview_scale: 20f32,
             scheduled_action: AppScheduledAction::None,
             config,
-            search_selection: String::new(),
+            tile_filter: TileFilter::default(),
+            read_only: false,
+            #[cfg(feature = "watch")]
+            watcher: None,
+            #[cfg(feature = "watch")]
+            watched_root: None,
+            #[cfg(feature = "watch")]
+            loaded_main_init: None,
+            #[cfg(feature = "watch")]
+            changed_on_disk: false,
             lhandle,
-        };
-
-        tileman_app.apply_loaded_data(maybe_init);
-        Ok(tileman_app)
-    }
-    fn load_data(root: std::path::PathBuf) -> Result<(TileInit, DeserErrorReports), AppError> {
-        let mut errors = Vec::new();
-        let additional_categories = lingo_de::collect_categories_from_subfolders(root.clone())
-            .unwrap_or(Vec::new())
-            .into_iter()
-            .map(|(category, newerrors)| {
-                for newerror in newerrors {
-                    errors.push(newerror)
-                }
-                category
-            })
-            .collect();
-        let text = std::fs::read_to_string(root.join("init.txt"));
-        match text {
-            Ok(text) => {
-                let init = lingo_de::parse_tile_init(text, additional_categories, root)?;
-                return Ok((init, errors));
-            }
-            Err(err) => Err(AppError::IOError(format!("{:?}", err))),
         }
     }
+    /// Pairs a loaded init with its main init text as it is on disk now
+    fn with_main_init_text(init: TileInit) -> Result<LoadedData, AppError> {
+        let text = lingo_de::read_init_text(&init.main_init_path())
+            .map_err(|err| AppError::IOError(format!("{:?}", err)))?;
+        Ok((init, text))
+    }
     fn apply_loaded_data(
         &mut self,
-        maybe_init: Result<(TileInit, Vec<(String, DeserError)>), AppError>,
+        maybe_init: Result<LoadedData, AppError>,
     ) {
+        self.changes_preview = None;
         match maybe_init {
-            Ok((actual_init, errors)) => {
-                //init = Some(actual_init);
-                self.init = Some(actual_init);
-                if !errors.is_empty() {
+            Ok((actual_init, original_text)) => {
+                if !actual_init.errored_lines.is_empty() {
                     log::error!(
-                        "Errors encountered when reading data (ignored on apply) : {errors:#?}\n"
+                        "Errors encountered when reading data (ignored on apply) : {:#?}\n",
+                        actual_init.errored_lines
+                    );
+                }
+                if let lingo_de::LineEndingReport::Mixed { lf, crlf } =
+                    lingo_de::detect_line_ending_consistency(&original_text)
+                {
+                    log::warn!(
+                        "Main init mixes line endings ({lf} LF, {crlf} CRLF), saving will write LF only"
                     );
                 }
+                //init = Some(actual_init);
+                self.init = Some(actual_init);
+                self.original_main_init = Some(original_text);
+                self.load_error = None;
             }
             Err(err) => {
-                self.init = None;
+                self.init = Some(TileInit::default());
+                self.original_main_init = None;
                 log::error!("Could not load data at all {err:?}");
+                self.load_error = Some(format!("{err:?}"));
             }
         };
+        #[cfg(feature = "watch")]
+        {
+            self.loaded_main_init = self.init.as_ref().map(lingo_ser::serialize_main_init);
+            self.changed_on_disk = false;
+        }
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+    /// The loaded pack, None until the first load is done
+    pub fn init(&self) -> Option<&TileInit> {
+        self.init.as_ref()
+    }
+    pub fn init_mut(&mut self) -> Option<&mut TileInit> {
+        self.init.as_mut()
+    }
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Loads whatever the path field points at. The selected tile stays selected if it still exists.
+    /// Several paths separated by `;` or new lines are merged with [`lingo_de::parse_tile_inits`] and stay read only.
+    pub fn reload(&mut self) {
+        let paths = split_path_list(&self.path_selection);
+        match paths.len() {
+            0 | 1 => self.reload_from(std::path::PathBuf::from(self.path_selection.trim())),
+            _ => self.load_merged(paths),
+        }
+    }
+
+    fn load_merged(&mut self, paths: Vec<std::path::PathBuf>) {
+        self.cancel_loading();
+        let files: Vec<std::path::PathBuf> = paths
+            .into_iter()
+            .map(|path| match path.is_dir() {
+                true => path.join("init.txt"),
+                false => path,
+            })
+            .collect();
+        self.apply_loaded_data(lingo_de::parse_tile_inits(&files).map(|init| (init, String::new())));
+        self.merged_files = files.len();
+        if self.load_error.is_none() {
+            push_recent_path(&mut self.recent_paths, self.path_selection.clone());
+        }
+        self.clear_selection_and_cache();
+    }
+
+    /// Nothing can be changed: the user asked for that, loading failed or is still running, or several inits were merged
+    fn edits_locked(&self) -> bool {
+        self.read_only || self.load_error.is_some() || self.merged_files > 1 || self.loading.is_some()
+    }
+
+    /// Starts loading `root` in the background, the current pack stays until it's done
+    fn reload_from(&mut self, root: std::path::PathBuf) {
+        self.cancel_loading();
+        self.loading = Some((
+            lingo_de::load_tile_init_in_background(root.clone(), Default::default()),
+            root,
+        ));
+    }
+
+    /// Drops a running load, whatever was loaded before stays as it is
+    fn cancel_loading(&mut self) {
+        if let Some((load, root)) = self.loading.take() {
+            load.cancel();
+            log::info!("cancelled loading {root:?}");
+        }
+    }
+
+    /// Takes over the background load once it's done, asks for another frame until then
+    fn poll_loading(&mut self, ctx: &egui::Context) {
+        let Some((load, _)) = &self.loading else {
+            return;
+        };
+        let Some(result) = load.try_take() else {
+            ctx.request_repaint_after(std::time::Duration::from_millis(50));
+            return;
+        };
+        let Some((_, root)) = self.loading.take() else {
+            return;
+        };
+        self.merged_files = 0;
+        self.apply_loaded_data(result.and_then(Self::with_main_init_text));
+        if self.load_error.is_none() {
+            push_recent_path(&mut self.recent_paths, root.to_string_lossy().into_owned());
+        }
+        self.config.root_path = root;
+        let selected_tile = self.selected_tile.filter(|(category_index, item_index)| {
+            self.init
+                .as_ref()
+                .and_then(|init| init.categories.get(*category_index))
+                .is_some_and(|category| *item_index < category.tiles.len())
+        });
+        self.clear_selection_and_cache();
+        self.selected_tile = selected_tile;
+    }
+
+    /// The pack was just written to disk, so it no longer counts as edited,
+    /// the file watcher reloads it instead of warning about unsaved edits and previews diff against what was written
+    pub(crate) fn mark_saved(&mut self) {
+        if let Some(init) = &self.init {
+            self.original_main_init = Some(
+                lingo_de::read_init_text(&init.main_init_path())
+                    .unwrap_or_else(|_| lingo_ser::serialize_main_init(init)),
+            );
+        }
+        self.changes_preview = None;
+        #[cfg(feature = "watch")]
+        {
+            self.loaded_main_init = self.init.as_ref().map(lingo_ser::serialize_main_init);
+            self.changed_on_disk = false;
+        }
+    }
+
+    /// Main init as it is on disk against what saving would write now
+    pub(crate) fn pending_changes(&self) -> Option<Vec<DiffLine>> {
+        self.init.as_ref().map(|init| {
+            diff_lines(
+                self.original_main_init.as_deref().unwrap_or(""),
+                &lingo_ser::serialize_main_init(init),
+            )
+        })
+    }
+
+    /// Whether the loaded pack was edited in the app since it was read or saved
+    #[cfg(feature = "watch")]
+    pub(crate) fn has_unsaved_changes(&self) -> bool {
+        self.init.as_ref().map(lingo_ser::serialize_main_init) != self.loaded_main_init
+    }
+
+    /// Follows the current root with a file watcher and reloads once changes on disk settle.
+    /// Without a watcher (the root is missing, or the platform refused) changes are picked up with Load as usual.
+    #[cfg(feature = "watch")]
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        if self.merged_files > 1 {
+            //a merged view isn't tied to the root, reloading it from there would replace it
+            return;
+        }
+        if self.watched_root.as_ref() != Some(&self.config.root_path) {
+            let root = self.config.root_path.clone();
+            self.watcher = match crate::watch::PackWatcher::new(&root, ctx.clone()) {
+                Ok(watcher) => Some(watcher),
+                Err(err) => {
+                    log::warn!("not watching {root:?} for changes: {err}");
+                    None
+                }
+            };
+            self.watched_root = Some(root);
+        }
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        if watcher.settled_change(std::time::Instant::now()) {
+            match self.has_unsaved_changes() {
+                true => self.changed_on_disk = true,
+                false => self.reload_from(self.config.root_path.clone()),
+            }
+        } else if watcher.is_pending() {
+            ctx.request_repaint_after(crate::watch::DEBOUNCE);
+        }
     }
 
     fn clear_selection_and_cache(&mut self) {
         self.selected_tile = None;
         self.selected_tile_cache = None;
-        self.preview_cache = None;
+        self.selected_index = None;
+        self.preview_cache.clear();
     }
 }
 
+/// Paths in the path field, which can list several separated by `;` or new lines
+pub fn split_path_list(text: &str) -> Vec<std::path::PathBuf> {
+    text.split([';', '\n'])
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(std::path::PathBuf::from)
+        .collect()
+}
+
+/// Moves `path` to the front of the list, dropping duplicates and anything past [`RECENT_PATHS_CAP`]
+pub fn push_recent_path(recent_paths: &mut VecDeque<String>, path: String) {
+    recent_paths.retain(|recent| *recent != path);
+    recent_paths.push_front(path);
+    recent_paths.truncate(RECENT_PATHS_CAP);
+}
+
 impl eframe::App for TilemanApp {
-    fn save(&mut self, _storage: &mut dyn eframe::Storage) {}
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, STORAGE_RECENT_PATHS_KEY, &self.recent_paths);
+    }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
         if let Err(err) = std::fs::write(
@@ -150,42 +507,122 @@ impl eframe::App for TilemanApp {
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_loading(ctx);
+        #[cfg(feature = "watch")]
+        self.poll_watcher(ctx);
+        // checked before anything is drawn, so enter in the path field doesn't also open a tile
+        let keys_free = !ctx.wants_keyboard_input();
         egui::TopBottomPanel::top("select_path").show(ctx, |ui| {
             ui.label("Path to init");
-            let text_input_response = ui.text_edit_singleline(&mut self.path_selection);
-            if text_input_response
-                .on_hover_text_at_pointer("Enter (copy&paste) path to your editor's tile directory")
-                .changed()
-            {
-                let root = std::path::PathBuf::from(self.path_selection.clone());
-                self.apply_loaded_data(Self::load_data(root.clone()));
-                self.config.root_path = root;
+            let mut path_changed = false;
+            ui.horizontal(|ui| {
+                let text_input_response = ui.text_edit_singleline(&mut self.path_selection);
+                path_changed = text_input_response.lost_focus()
+                    && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                text_input_response
+                    .on_hover_text_at_pointer("Enter (copy&paste) path to your editor's tile directory");
+                if ui.button("Load")
+                    .on_hover_text_at_pointer("Load tiles from the path above")
+                    .clicked() {
+                    path_changed = true;
+                }
+                egui::ComboBox::from_id_source("recent_paths")
+                    .selected_text("Recent")
+                    .show_ui(ui, |ui| {
+                        for recent in self.recent_paths.iter() {
+                            if ui.selectable_label(false, recent.as_str()).clicked() {
+                                self.path_selection = recent.clone();
+                                path_changed = true;
+                            }
+                        }
+                    });
+                ui.checkbox(&mut self.read_only, "Read only")
+                    .on_hover_text_at_pointer("Inspect the pack without being able to edit or save it");
+                if let (None, Some(init)) = (&self.load_error, &self.init) {
+                    let stats = init.stats();
+                    let per_type = TileType::ALL
+                        .iter()
+                        .filter_map(|tile_type| {
+                            let count = stats.tiles_per_type.get(tile_type)?;
+                            Some(format!("{tile_type}: {count}"))
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    ui.label(stats.to_string()).on_hover_text(per_type);
+                }
+            });
+            let mut cancel_loading = false;
+            if let Some((load, root)) = &self.loading {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::ProgressBar::new(load.progress())
+                            .desired_width(240.0)
+                            .text(format!("Loading {}", root.display())),
+                    );
+                    cancel_loading = ui
+                        .button("Cancel")
+                        .on_hover_text_at_pointer("Stop loading and keep what was loaded before")
+                        .clicked();
+                });
+            }
+            if cancel_loading {
+                self.cancel_loading();
+            }
+            if let Some(err) = &self.load_error {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("Could not load tiles from this path: {err}"),
+                );
+            } else if self.merged_files > 1 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Merged from {} inits, read only", self.merged_files),
+                );
+            }
+            #[cfg(feature = "watch")]
+            if self.changed_on_disk {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "Files changed on disk. Load picks them up, but throws away unsaved edits",
+                );
+            }
+            if path_changed {
+                self.reload();
             }
         });
-        let output_path = &mut self.config.output_path;
+        let read_only = self.edits_locked();
         let selected_tile = &mut self.selected_tile;
-        let selected_tile_cache = &mut self.selected_tile_cache;
-        let maybe_preview_cache = &mut self.preview_cache;
+        let selected_index = &mut self.selected_index;
+        let preview_cache = &mut self.preview_cache;
         let preview_scale = &mut self.preview_scale;
         //let reload_scheduled = &mut self.reload_scheduled;
         let scheduled_action = &mut self.scheduled_action;
-        let search_selection = &mut self.search_selection;
+        let tile_filter = &mut self.tile_filter;
         match &mut self.init {
             Some(init) => {
                 //draw action buttons
                 egui::TopBottomPanel::top("action_buttons").show(ctx, |ui| {
-                    draw_toolbox(ctx, ui, init, preview_scale, scheduled_action, output_path)
+                    draw_toolbox(ctx, ui, init, preview_scale, scheduled_action, read_only)
                 });
+                //draw parse errors
+                if !init.errored_lines.is_empty() {
+                    egui::TopBottomPanel::bottom("parse_errors")
+                        .resizable(true)
+                        .show(ctx, |ui| draw_parse_errors(ui, &init.errored_lines));
+                }
                 //draw tile list
                 egui::SidePanel::left("tile_list").show(ctx, |ui| {
                     draw_tiles_panel(
-                        ctx,
                         ui,
                         init,
-                        selected_tile,
-                        selected_tile_cache,
-                        scheduled_action,
-                        search_selection,
+                        TilesPanel {
+                            selected_tile,
+                            selected_index,
+                            scheduled_action,
+                            tile_filter,
+                            read_only,
+                            keys_free,
+                        },
                     );
                     //ui.set_width(width)
                 });
@@ -195,45 +632,65 @@ impl eframe::App for TilemanApp {
                         ctx,
                         ui,
                         selected_tile,
-                        selected_tile_cache,
                         init,
-                        maybe_preview_cache,
+                        preview_cache,
                         preview_scale,
+                        read_only,
                     );
                 });
             }
             None => {
                 self.selected_tile_cache = None;
-                self.preview_cache = None;
+                self.preview_cache.clear();
+            }
+        }
+        if let Some(changes) = &self.changes_preview {
+            let mut open = true;
+            egui::Window::new("Preview changes")
+                .open(&mut open)
+                .vscroll(true)
+                .show(ctx, |ui| draw_changes_preview(ui, changes));
+            if !open {
+                self.changes_preview = None;
             }
         }
         self.selected_tile_cache = self.selected_tile.clone();
 
+        if self.edits_locked() && self.scheduled_action.edits_pack() {
+            log::info!("read only, ignoring {:?}", self.scheduled_action);
+            self.scheduled_action = AppScheduledAction::None;
+        }
         match self.scheduled_action.clone() {
             AppScheduledAction::None => {}
-            AppScheduledAction::Reload => {
-                self.apply_loaded_data(Self::load_data(std::path::PathBuf::from(
-                    self.path_selection.clone(),
-                )));
-                self.clear_selection_and_cache();
+            AppScheduledAction::Reload => self.reload(),
+            AppScheduledAction::Saved => {
+                self.mark_saved();
+                #[cfg(feature = "watch")]
+                let watched = self.watcher.is_some();
+                #[cfg(not(feature = "watch"))]
+                let watched = false;
+                //the watcher reloads once the writes settle, otherwise reload the pack that was saved.
+                //not the path field, it may have been edited since
+                if !watched {
+                    self.reload_from(self.config.root_path.clone());
+                }
             }
             AppScheduledAction::MoveCategory(old_index, by) => {
                 if let Some(init) = &mut self.init {
+                    let start_index = init.lowest_category_index();
                     let new_index = (old_index as i32 + by).max(0) as usize;
                     log::debug!("moving {old_index} by {by}");
-                    init.categories.get_mut(old_index).and_then(|a| {
-                        a.index = new_index;
-                        Some(a)
-                    });
-                    init.categories.get_mut(new_index).and_then(|a| {
-                        a.index = old_index;
-                        Some(a)
-                    });
-
-                    init.sort_and_normalize_categories();
+                    //swap the indices the two categories have, positions only match them when counting from 0
+                    if old_index < init.categories.len() && new_index < init.categories.len() {
+                        let moved = init.categories[old_index].index;
+                        init.categories[old_index].index = init.categories[new_index].index;
+                        init.categories[new_index].index = moved;
+                    }
+                    init.sort_and_normalize_categories_from(start_index);
                 }
                 self.clear_selection_and_cache();
             }
+            AppScheduledAction::PreviewChanges => self.changes_preview = self.pending_changes(),
             AppScheduledAction::DisplayMessage { icon, title, text } => {
                 
                 let msgbox_res = msgbox::create(&title, &text, icon);
@@ -254,13 +711,75 @@ fn draw_central_panel(
     ctx: &egui::Context,
     ui: &mut egui::Ui,
     selected_tile: &mut Option<(usize, usize)>,
-    selected_tile_cache: &mut Option<(usize, usize)>,
     init: &mut TileInit,
-    maybe_preview_cache: &mut Option<PreviewCache>,
+    preview_cache: &mut LruCache<(usize, usize), PreviewCache>,
     preview_scale: &mut f32,
+    read_only: bool,
 ) {
-    //let (old_categpory_index, old_item_index) = selected_tile_cache.unwrap_or((usize::MAX, usize::MAX));
-    let changed_selection = *selected_tile != *selected_tile_cache;
+    let clipboard_id = egui::Id::new("tile_clipboard");
+    if let Some((category_index, item_index)) = *selected_tile {
+        let category_names: Vec<String> =
+            init.categories.iter().map(|category| category.name.clone()).collect();
+        let mut move_to = None;
+        ui.horizontal(|ui| {
+            let Some(category) = init.categories.get_mut(category_index) else {
+                return;
+            };
+            if let Some(item) = category.tiles.get(item_index) {
+                if ui
+                    .button("Copy")
+                    .on_hover_text_at_pointer("Copy the tile as an init line, to paste into any category")
+                    .clicked()
+                {
+                    let line = lingo_ser::serialize_tile_info(item);
+                    ui.output_mut(|output| output.copied_text = line.clone());
+                    ui.data_mut(|data| data.insert_temp(clipboard_id, line));
+                }
+            }
+            let copied: Option<String> = ui.data(|data| data.get_temp(clipboard_id));
+            let paste = ui
+                .add_enabled(!read_only && copied.is_some(), egui::Button::new("Paste"))
+                .on_hover_text_at_pointer("Add the copied tile to this tile's category, renamed if the name is taken");
+            if let (true, Some(line)) = (paste.clicked(), copied) {
+                match category.paste_tile(&line) {
+                    Ok(new_index) => {
+                        *selected_tile = Some((category_index, new_index));
+                        preview_cache.clear();
+                    }
+                    Err(err) => log::error!("could not paste {line:?}: {err:?}"),
+                }
+            }
+            let Some(item) = category.tiles.get(item_index) else {
+                return;
+            };
+            ui.add_enabled_ui(!read_only, |ui| {
+                egui::ComboBox::from_id_source("move_tile")
+                    .selected_text("Move to")
+                    .show_ui(ui, |ui| {
+                        for name in category_names.iter().filter(|name| **name != category.name) {
+                            if ui.selectable_label(false, name.as_str()).clicked() {
+                                move_to = Some((item.name.clone(), category.name.clone(), name.clone()));
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text_at_pointer("Move the tile to the end of another category");
+            });
+        });
+        if let Some((tile_name, from, to)) = move_to {
+            match init.move_tile(&tile_name, &from, &to) {
+                Ok(()) => {
+                    *selected_tile = init
+                        .categories
+                        .iter()
+                        .position(|category| category.name == to)
+                        .map(|to_index| (to_index, init.categories[to_index].tiles.len() - 1));
+                    preview_cache.clear();
+                }
+                Err(err) => log::error!("could not move {tile_name} to {to}: {err:?}"),
+            }
+        }
+    }
     match selected_tile {
         Some((category_index, item_index)) => {
             if let Some(Some(item)) = init
@@ -273,8 +792,9 @@ fn draw_central_panel(
                     ui,
                     preview_scale,
                     item,
-                    maybe_preview_cache,
-                    changed_selection,
+                    preview_cache,
+                    (*category_index, *item_index),
+                    read_only,
                 );
             }
         }
@@ -287,39 +807,30 @@ fn draw_tile_details(
     ui: &mut egui::Ui,
     preview_scale: &mut f32,
     item: &mut TileInfo,
-    maybe_preview_cache: &mut Option<PreviewCache>,
-    changed_selection: bool,
+    preview_cache: &mut LruCache<(usize, usize), PreviewCache>,
+    cache_key: (usize, usize),
+    read_only: bool,
 ) {
-    ui.heading(item.name.clone());
+    let cached = preview_cache.get(&cache_key).cloned();
+    let thumbnail = cached
+        .as_ref()
+        .map(|thandle| thandle.thumbnail.clone())
+        .unwrap_or_else(|| create_thumbnail_texture(ctx, item));
+    ui.horizontal(|ui| {
+        ui.add(egui::Image::from_texture(&thumbnail).fit_to_exact_size(thumbnail.size_vec2() * 4.0))
+            .on_hover_text("footprint from specs1");
+        ui.heading(item.name.clone());
+    });
     ui.label(format!(
         "size: {:?}\n repeatL: {:?}\n random vars: {:?}\n", 
         item.size, 
-        item.repeat_layers, 
+        item.repeat_layers.as_ref().map(|layers| &layers.0),
         item.random_vars));
     //ui.label(format!({}, ))
-    let mut default_string = String::new();
     egui::ScrollArea::vertical()
         .id_source("edit_tags_section")
         .show(ui, |ui| {
-            let mut maybe_remove = None;
-            for tag_index in indices(&item.tags) {
-                ui.horizontal(|ui| {
-                    ui.text_edit_singleline(
-                        item.tags.get_mut(tag_index).unwrap_or(&mut default_string),
-                    );
-                    if ui.button("remove").clicked() {
-                        maybe_remove = Some(tag_index);
-                        log::debug!("removing tag {tag_index} from {}", item.name.clone())
-                    }
-                });
-            }
-            if (ui.button("Add tag")).clicked() {
-                item.tags.push(String::new());
-                log::debug!("adding tag to {}", item.name.clone())
-            }
-            if let Some(remove) = maybe_remove {
-                item.tags.remove(remove);
-            }
+            ui.add_enabled_ui(!read_only, |ui| draw_tag_editor(ui, item));
 
             egui::ScrollArea::horizontal()
                 .id_source("preview_specs_section")
@@ -330,12 +841,10 @@ fn draw_tile_details(
                     );
                     ui.heading("specs1");
 
-                    let maybe_thandle_s1 = match (maybe_preview_cache.clone(), changed_selection) {
-                        (Some(thandle), false) => Some(thandle.specs),
-                        _ => None,
-                    };
-                    let thandle_s1 =
-                        maybe_thandle_s1.unwrap_or_else(|| create_specs_texture(ctx, item, false));
+                    let thandle_s1 = cached
+                        .as_ref()
+                        .map(|thandle| thandle.specs.clone())
+                        .unwrap_or_else(|| create_specs_texture(ctx, item, false));
 
                     ui.add(
                         egui::Image::from_texture(&thandle_s1).fit_to_exact_size(
@@ -346,12 +855,8 @@ fn draw_tile_details(
                         )
                     );
 
-                    let mut maybe_thandle_s2 =
-                        match (maybe_preview_cache.clone(), changed_selection) {
-                            (Some(thandle), false) => thandle.specs2,
-                            _ => None,
-                        };
-                    if item.specs2.is_some() {
+                    let mut maybe_thandle_s2 = cached.and_then(|thandle| thandle.specs2);
+                    if item.specs2.layer().is_some() {
                         ui.heading("specs2");
                         maybe_thandle_s2 = maybe_thandle_s2
                             .or_else(|| Some(create_specs_texture(ctx, item, true)));
@@ -364,10 +869,14 @@ fn draw_tile_details(
                             )
                         );
                     }
-                    *maybe_preview_cache = Some(PreviewCache {
-                        specs: thandle_s1,
-                        specs2: maybe_thandle_s2,
-                    })
+                    preview_cache.insert(
+                        cache_key,
+                        PreviewCache {
+                            specs: thandle_s1,
+                            specs2: maybe_thandle_s2,
+                            thumbnail: thumbnail.clone(),
+                        },
+                    )
                 });
         });
 }
@@ -430,136 +939,313 @@ fn create_specs_texture(
     ctx.load_texture(name, image, options)
 }
 
-fn draw_tiles_panel(
-    ctx: &egui::Context,
-    ui: &mut egui::Ui,
-    init: &mut TileInit,
-    selected_tile: &mut Option<(usize, usize)>,
-    selected_tile_cache: &mut Option<(usize, usize)>,
-    scheduled_action: &mut AppScheduledAction,
-    search_selection: &mut String,
-) {
+/// One pixel per cell, see [`TileInfo::preview_thumbnail`]
+fn create_thumbnail_texture(ctx: &egui::Context, item: &TileInfo) -> egui::TextureHandle {
+    let thumbnail = item.preview_thumbnail(false);
+    if thumbnail.mismatched {
+        log::warn!("specs of {} don't match its size {:?}", item.name, item.size);
+    }
+    let pixels = thumbnail
+        .pixels
+        .iter()
+        .map(|[r, g, b, a]| egui::Color32::from_rgba_unmultiplied(*r, *g, *b, *a))
+        .collect();
+    let image = egui::ColorImage {
+        size: [thumbnail.width, thumbnail.height],
+        pixels,
+    };
+    let options = egui::TextureOptions {
+        magnification: egui::TextureFilter::Nearest,
+        ..Default::default()
+    };
+    ctx.load_texture(format!("{}-thumbnail", item.name), image, options)
+}
+
+/// Tags as chips that remove themselves when clicked, plus a field for adding one
+fn draw_tag_editor(ui: &mut egui::Ui, item: &mut TileInfo) {
+    let mut maybe_remove = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.label("tags");
+        for tag in item.tags.iter() {
+            if ui
+                .small_button(format!("{tag}  x"))
+                .on_hover_text_at_pointer("Remove tag")
+                .clicked()
+            {
+                maybe_remove = Some(tag.clone());
+            }
+        }
+    });
+    if let Some(tag) = maybe_remove {
+        log::debug!("removing tag {tag} from {}", item.name);
+        item.remove_tag(&tag);
+    }
+    //the half typed tag lives in egui's memory so it survives between frames
+    let input_id = ui.make_persistent_id("new_tag_input");
+    let mut new_tag = ui.data_mut(|data| data.get_temp::<String>(input_id).unwrap_or_default());
+    ui.horizontal(|ui| {
+        let response = ui.text_edit_singleline(&mut new_tag);
+        let submitted = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+        if ui.button("Add tag").clicked() || submitted {
+            if item.add_tag(&new_tag) {
+                log::debug!("adding tag {new_tag} to {}", item.name);
+                new_tag.clear();
+            } else {
+                log::debug!("not adding tag {new_tag:?} to {}, it's empty or already there", item.name);
+            }
+        }
+    });
+    ui.data_mut(|data| data.insert_temp(input_id, new_tag));
+}
+
+/// App state the tile list panel reads and writes, besides the init itself
+struct TilesPanel<'a> {
+    selected_tile: &'a mut Option<(usize, usize)>,
+    selected_index: &'a mut Option<usize>,
+    scheduled_action: &'a mut AppScheduledAction,
+    tile_filter: &'a mut TileFilter,
+    read_only: bool,
+    keys_free: bool, //no text field has focus, so arrow keys can move the cursor
+}
+
+fn draw_tiles_panel(ui: &mut egui::Ui, init: &mut TileInit, panel: TilesPanel) {
+    let TilesPanel {
+        selected_tile,
+        selected_index,
+        scheduled_action,
+        tile_filter,
+        read_only,
+        keys_free,
+    } = panel;
     ui.label("search");
-    ui.text_edit_singleline(search_selection)
+    ui.text_edit_singleline(&mut tile_filter.search)
         .on_hover_text_at_pointer("Search tiles");
+    ui.checkbox(&mut tile_filter.search_tags, "Search tags")
+        .on_hover_text_at_pointer("Also show tiles with a matching tag");
+    ui.checkbox(&mut tile_filter.fuzzy, "Fuzzy search")
+        .on_hover_text_at_pointer("Match letters in order with gaps, so \"drpbg\" finds \"Drip Big\". Best matches come first");
+    ui.checkbox(&mut tile_filter.hide_inactive, "Hide inactive")
+        .on_hover_text_at_pointer("Hide disabled tiles instead of greying them out");
+    ui.horizontal(|ui| {
+        ui.label("sort by");
+        ui.selectable_value(&mut tile_filter.sort_key, SortKey::Index, "Index")
+            .on_hover_text_at_pointer("The order the game lists categories in");
+        ui.selectable_value(&mut tile_filter.sort_key, SortKey::Name, "Name")
+            .on_hover_text_at_pointer("Alphabetical, saving still keeps the game's order");
+    });
     ui.heading("tiles");
+    let visible_tiles = tile_filter.visible_tiles(init);
+    // arrow keys move through the list, enter opens
+    let (up, down, enter) = match keys_free {
+        true => ui.input(|input| {
+            (
+                input.key_pressed(egui::Key::ArrowUp),
+                input.key_pressed(egui::Key::ArrowDown),
+                input.key_pressed(egui::Key::Enter),
+            )
+        }),
+        false => (false, false, false),
+    };
+    let cursor_moved = up || down;
+    if cursor_moved {
+        *selected_index = step_index(*selected_index, visible_tiles.len(), down);
+    }
+    *selected_index = selected_index.filter(|index| *index < visible_tiles.len());
+    let cursor = selected_index.map(|index| visible_tiles[index]);
+    if enter && cursor.is_some() {
+        *selected_tile = cursor;
+    }
+    let mut navigation = TileCursor {
+        cursor,
+        moved: cursor_moved,
+        visible_tiles: &visible_tiles,
+        selected_index,
+        selected_tile,
+    };
+    let visible_categories = tile_filter.visible_categories(init);
+    let category_order = tile_filter.category_order(init);
     egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
-        for category_index in indices(&init.categories) {
+        for category_index in category_order {
             let category = &mut init.categories[category_index];
-            egui::CollapsingHeader::new(category.name.as_str())
-                .show(ui, |ui| {
+            if visible_categories
+                .as_ref()
+                .is_some_and(|visible| !visible.contains(&category.name))
+            {
+                continue;
+            }
+            let [r, g, b] = category.color;
+            let header_text = egui::RichText::new(category.name.as_str())
+                .color(category.text_color_for_contrast())
+                .background_color(egui::Color32::from_rgb(r, g, b));
+            let id = ui.make_persistent_id(category.name.as_str());
+            let mut collapsing =
+                egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false);
+            if navigation.moved && navigation.cursor.is_some_and(|(index, _)| index == category_index) {
+                collapsing.set_open(true);
+            }
+            collapsing
+                .show_header(ui, |ui| {
+                    ui.add_enabled_ui(!read_only, |ui| {
+                        //main init only categories can't hold inactive tiles, same as the per tile checkbox
+                        if category.subfolder.is_some() {
+                            let tiles_active = category.tiles_active();
+                            let mut all_active = tiles_active == Some(true);
+                            let checkbox = egui::Checkbox::without_text(&mut all_active)
+                                .indeterminate(tiles_active.is_none());
+                            if ui
+                                .add(checkbox)
+                                .on_hover_text_at_pointer("Set every tile in this category active or inactive")
+                                .changed()
+                            {
+                                category.set_tiles_active(all_active);
+                            }
+                        }
+                        egui::color_picker::color_edit_button_srgb(ui, &mut category.color)
+                            .on_hover_text_at_pointer("Category color");
+                    });
+                    ui.label(header_text)
+                        .on_hover_text_at_pointer(match category.subfolder {
+                            Some(_) => "A subfolder",
+                            None => "Exists in main init only",
+                        });
+                })
+                .body(|ui| {
                     list_tile_category(
-                        ctx,
                         ui,
-                        &mut init.root,
                         category,
-                        selected_tile,
-                        selected_tile_cache,
+                        &mut navigation,
                         category_index,
                         scheduled_action,
-                        search_selection,
+                        tile_filter,
+                        read_only,
                     );
-                })
-                .header_response
-                .on_hover_text_at_pointer(match category.subfolder {
-                    Some(_) => "A subfolder",
-                    None => "Exists in main init only",
                 });
         }
     });
 }
 
+/// Where the keyboard cursor is in the tile list
+struct TileCursor<'a> {
+    cursor: Option<(usize, usize)>,
+    moved: bool, //this frame, so the list scrolls to it
+    visible_tiles: &'a [(usize, usize)],
+    selected_index: &'a mut Option<usize>,
+    selected_tile: &'a mut Option<(usize, usize)>, //opened in the central panel
+}
+
+/// One step up or down a list of `len` items, stopping at the ends. Nothing selected starts at the top or bottom.
+pub(crate) fn step_index(current: Option<usize>, len: usize, down: bool) -> Option<usize> {
+    let last = len.checked_sub(1)?;
+    Some(match (current, down) {
+        (None, true) => 0,
+        (None, false) => last,
+        (Some(index), true) => (index + 1).min(last),
+        (Some(index), false) => index.saturating_sub(1).min(last),
+    })
+}
+
 fn list_tile_category(
-    _ctx: &egui::Context,
     ui: &mut egui::Ui,
-    _root: &mut std::path::PathBuf,
     category: &mut crate::TileCategory,
-    selected_tile: &mut Option<(usize, usize)>,
-    _selected_tile_cache: &mut Option<(usize, usize)>,
+    navigation: &mut TileCursor,
     category_index: usize,
     scheduled_action: &mut AppScheduledAction,
-    search_selection: &mut String,
+    tile_filter: &TileFilter,
+    read_only: bool,
 ) {
     let is_folder = category.subfolder.is_some();
-    //ui.text_edit_singleline(&mut category.name);
-    if is_folder {
-        ui.checkbox(&mut category.enabled, "Enable category");
-    }
-    //format!("{}_change", category.name.clone()),
-    egui::ComboBox::from_label("Change")
-        .selected_text(format!("{:?}", category.scheduled_change))
-        .show_ui(ui, |ui| {
-            macro_rules! add_choice {
-                ($item:ident) => {
-                    ui.selectable_value(
-                        &mut category.scheduled_change,
-                        TileCategoryChange::$item,
-                        stringify!($item),
-                    );
-                };
+    ui.add_enabled_ui(!read_only, |ui| {
+        //ui.text_edit_singleline(&mut category.name);
+        if is_folder {
+            ui.checkbox(&mut category.enabled, "Enable category");
+        }
+        //format!("{}_change", category.name.clone()),
+        egui::ComboBox::from_label("Change")
+            .selected_text(format!("{:?}", category.scheduled_change))
+            .show_ui(ui, |ui| {
+                macro_rules! add_choice {
+                    ($item:ident) => {
+                        ui.selectable_value(
+                            &mut category.scheduled_change,
+                            TileCategoryChange::$item,
+                            stringify!($item),
+                        );
+                    };
+                }
+                add_choice!(None);
+                add_choice!(MoveFromSubfolder);
+                add_choice!(MoveToSubfolder);
+                add_choice!(Delete);
+            });
+        let can_move = tile_filter.sort_key == SortKey::Index;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(can_move, egui::Button::new("[ ^ ]"))
+                .on_hover_text_at_pointer("Move category up")
+                .on_disabled_hover_text("Sort by index to move categories")
+                .clicked()
+            {
+                *scheduled_action = AppScheduledAction::MoveCategory(category_index, -1);
+            }
+            if ui
+                .add_enabled(can_move, egui::Button::new("[ v ]"))
+                .on_hover_text("Move category down")
+                .on_disabled_hover_text("Sort by index to move categories")
+                .clicked()
+            {
+                *scheduled_action = AppScheduledAction::MoveCategory(category_index, 1);
             }
-            add_choice!(None);
-            add_choice!(MoveFromSubfolder);
-            add_choice!(MoveToSubfolder);
-            add_choice!(Delete);
         });
-    ui.horizontal(|ui| {
-        if ui
-            .button("[ ^ ]")
-            .on_hover_text_at_pointer("Move category up")
-            .clicked()
-        {
-            *scheduled_action = AppScheduledAction::MoveCategory(category_index, -1);
-        }
-        if ui
-            .button("[ v ]")
-            .on_hover_text("Move category down")
-            .clicked()
-        {
-            *scheduled_action = AppScheduledAction::MoveCategory(category_index, 1);
-        }
     });
-    for item_index in indices(&category.tiles) {
+    for item_index in tile_filter.tile_order(category) {
         let item = &mut category.tiles[item_index];
-        if !tile_info_matches_search(item, search_selection) {
-            continue;
-        }
+        let position = (category_index, item_index);
         ui.horizontal(|ui| {
             if is_folder {
-                ui.checkbox(&mut item.active, "");
+                ui.add_enabled(!read_only, egui::Checkbox::new(&mut item.active, ""));
+            }
+            let label = match item.active {
+                true => egui::RichText::new(item.name.as_str()),
+                false => egui::RichText::new(item.name.as_str()).weak(),
+            };
+            let under_cursor = navigation.cursor == Some(position);
+            let response = ui
+                .add(egui::Button::new(label).selected(under_cursor))
+                .on_hover_text_at_pointer(item.to_string());
+            if under_cursor && navigation.moved {
+                response.scroll_to_me(Some(egui::Align::Center));
             }
-            if ui.button(item.name.as_str()).clicked() {
-                *selected_tile = Some((category_index, item_index));
+            if response.clicked() {
+                *navigation.selected_tile = Some(position);
+                *navigation.selected_index =
+                    navigation.visible_tiles.iter().position(|visible| *visible == position);
             };
         });
     }
 }
 
-fn tile_info_matches_search(item: &TileInfo, search_selection: &String) -> bool {
-    if search_selection.is_empty() {
-        return true;
-    }
-    name_matches_search(&item.name, search_selection)
-        || item
-            .tags
-            .iter()
-            .any(|tag| name_matches_search(tag, search_selection))
-}
-
 fn draw_toolbox(
     _ctx: &egui::Context,
     ui: &mut egui::Ui,
     init: &mut TileInit,
     preview_scale: &mut f32,
     scheduled_action: &mut AppScheduledAction,
-    output_path: &mut std::path::PathBuf,
+    read_only: bool,
 ) {
     ui.horizontal(|ui| {
-        if ui.button("save inits")
+        if ui.add_enabled(!read_only, egui::Button::new("save inits"))
             .on_hover_text_at_pointer("Write main and subfolder inits to disk")
+            .on_disabled_hover_text("Read only mode is on, or nothing was loaded")
             .clicked() {
             
-            if let Err((err, _)) = lingo_ser::rewrite_init(init, output_path.clone()) {
+            if let Err(problems) = init.validate_for_save() {
+                log::error!("refusing to save, pack has problems: {problems:#?}");
+                *scheduled_action = AppScheduledAction::DisplayMessage {
+                    icon: msgbox::IconType::Error,
+                    title: String::from("Pack not saved"),
+                    text: format!("fix these before saving:\n{}", problems.join("\n")),
+                };
+            } else if let Err(err) = init.save() {
                 *scheduled_action = AppScheduledAction::DisplayMessage {
                     icon: msgbox::IconType::Error,
                     title: String::from("Error saving inits"),
@@ -567,7 +1253,7 @@ fn draw_toolbox(
                 };
                 //format!("failed to save inits to disk due to the following error: {err:?}. details in tileman.log")
             } else {
-                *scheduled_action = AppScheduledAction::Reload;
+                *scheduled_action = AppScheduledAction::Saved;
                 //log::info!("saved with result {:#?}", result)
             }
         };
@@ -590,6 +1276,12 @@ fn draw_toolbox(
             }
         }
 
+        if ui.button("preview changes")
+            .on_hover_text_at_pointer("Compare the main init as it would be saved against the one loaded from disk")
+            .clicked() {
+            *scheduled_action = AppScheduledAction::PreviewChanges;
+        }
+
         ui.add(egui::Slider::new(preview_scale, 5f32..=40f32))
             .on_hover_text_at_pointer("Select tile preview scale");
 
@@ -617,3 +1309,39 @@ fn draw_toolbox(
         //};
     });
 }
+
+fn draw_parse_errors(ui: &mut egui::Ui, errored_lines: &[lingo_de::DeserErrorReport]) {
+    egui::CollapsingHeader::new(format!("{} lines could not be read", errored_lines.len()))
+        .id_source("parse_errors")
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+                for report in errored_lines {
+                    if ui
+                        .selectable_label(false, report.to_string())
+                        .on_hover_text_at_pointer(format!("{}\nClick to copy the line", report.text))
+                        .clicked()
+                    {
+                        ui.output_mut(|output| output.copied_text = report.text.clone());
+                    }
+                }
+            });
+        });
+}
+
+fn draw_changes_preview(ui: &mut egui::Ui, changes: &[DiffLine]) {
+    if !changes.iter().any(|line| !matches!(line, DiffLine::Same(_))) {
+        ui.label("No changes to the main init");
+        return;
+    }
+    for line in changes {
+        match line {
+            DiffLine::Same(text) => ui.weak(format!("  {text}")),
+            DiffLine::Added(text) => {
+                ui.colored_label(egui::Color32::LIGHT_GREEN, format!("+ {text}"))
+            }
+            DiffLine::Removed(text) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("- {text}"))
+            }
+        };
+    }
+}
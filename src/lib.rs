@@ -1,35 +1,51 @@
 
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use cycle_map::{CycleMap, GroupMap};
 use include_dir::{include_dir, Dir};
 use lingo_de::DeserError;
 use lingo_ser::SerError;
-use utl::indices;
+use utl::{compare_names, fuzzy_score, indices};
 
+#[cfg(feature = "gui")]
 pub mod app;
 pub mod lingo_de;
 pub mod lingo_ser;
 mod utl;
+#[cfg(feature = "watch")]
+mod watch;
 
-type DeserErrorReports = Vec<(String, DeserError)>;
+type DeserErrorReports = Vec<lingo_de::DeserErrorReport>;
 type SerErrorReports = Vec<SerError>;
 type PrimitiveColor = [u8; 3];
 
 #[cfg(test)]
+#[allow(deprecated)] //ser_and_deser still goes through lingo_ser::serialize_tileinfo
 mod tests;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TileType {
     VoxelStruct,
     VoxelStructRockType,
     VoxelStructDisplaceV,
     VoxelStructDisplaceH,
+    VoxelStructSandType, //drought
     Box,
 }
 
+/// Game releases in order, each one reads everything the earlier ones do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameVersion {
+    Vanilla,
+    Downpour,
+    Drought,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TileCell {
     Any,
     Air,
@@ -43,14 +59,50 @@ pub enum TileCell {
     Glass,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A plain RGBA image, row by row, so previews can be drawn without a GUI
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Thumbnail {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>,
+    /// the specs don't have exactly one cell per tile of the declared size
+    pub mismatched: bool,
+}
+
+impl Thumbnail {
+    /// alternated on cells that don't line up with the declared size
+    pub const ERROR_COLORS: [[u8; 4]; 2] = [[255, 0, 255, 255], [64, 0, 64, 255]];
+
+    fn error(width: usize, height: usize) -> Self {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Self::ERROR_COLORS[(x + y) % 2]))
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+            mismatched: true,
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        (x < self.width).then(|| self.pixels.get(y * self.width + x).copied()).flatten()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileInit {
     pub root: std::path::PathBuf,
     pub categories: Vec<TileCategory>,
     pub errored_lines: DeserErrorReports,
+    /// comment lines after the last category or tile of the main init, written back at the end
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub trailing_comments: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TileCategoryChange {
     None,
     MoveToSubfolder,
@@ -58,7 +110,94 @@ pub enum TileCategoryChange {
     Delete,
 }
 
+/// Order to list categories in, see [`TileInit::sort_categories`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    /// the order the game sees them in
+    #[default]
+    Index,
+    /// alphabetical, ignoring case
+    Name,
+}
+
+/// Everything that differs between two tilesets, see [`TileInit::diff`].
+/// Categories and tiles are matched by name, so a tile moved to another category shows up as removed and added.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TilesetDiff {
+    pub added_categories: Vec<String>,
+    pub removed_categories: Vec<String>,
+    pub modified_categories: Vec<CategoryChange>,
+    /// `(category, tile)`
+    pub added_tiles: Vec<(String, String)>,
+    /// `(category, tile)`
+    pub removed_tiles: Vec<(String, String)>,
+    pub modified_tiles: Vec<TileChange>,
+}
+
+/// A category on both sides with different settings. `fields` are the [`TileCategory`] field names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryChange {
+    pub name: String,
+    pub fields: Vec<&'static str>,
+}
+
+/// A tile on both sides that isn't the same anymore. `fields` are the [`TileInfo`] field names, see [`TileInfo::changed_fields`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileChange {
+    pub category: String,
+    pub name: String,
+    pub fields: Vec<&'static str>,
+}
+
+impl TilesetDiff {
+    /// Number of added, removed and modified categories and tiles together
+    pub fn len(&self) -> usize {
+        self.added_categories.len()
+            + self.removed_categories.len()
+            + self.modified_categories.len()
+            + self.added_tiles.len()
+            + self.removed_tiles.len()
+            + self.modified_tiles.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Counts for a quick look over a tileset, see [`TileInit::stats`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TilesetStats {
+    pub categories: usize,
+    pub disabled_categories: usize,
+    pub tiles: usize,
+    /// only types that are used at all
+    pub tiles_per_type: HashMap<TileType, usize>,
+    pub errored_lines: usize,
+}
+
+impl std::fmt::Display for TilesetStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} categories ({} disabled), {} tiles, {} errored lines",
+            self.categories, self.disabled_categories, self.tiles, self.errored_lines
+        )
+    }
+}
+
+/// What [`TileInit::merge`] does when both sides have a category or tile with the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// the incoming init wins, category settings included
+    PreferOther,
+    /// the existing init wins, only new categories and tiles are added
+    PreferSelf,
+    /// existing category settings stay, clashing tiles that differ get added under a numbered name
+    KeepBoth,
+}
+
 #[derive(Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileCategory {
     pub index: usize,
     pub enabled: bool,
@@ -67,27 +206,120 @@ pub struct TileCategory {
     pub color: PrimitiveColor,
     pub tiles: Vec<TileInfo>,
     pub scheduled_change: TileCategoryChange, //pub scheduled_move_to_sub: bool
+    /// comment lines right before the header, as written, saved back in front of it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub comments: Vec<String>,
 }
 
 #[derive(Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileInfo {
     pub active: bool,
     pub name: String,                    //nm
     pub size: Vec<i32>,                  //sz
-    pub specs: Vec<TileCell>,            //specs
-    pub specs2: Option<Vec<TileCell>>,   //specs2
+    pub specs: SpecData,                 //specs
+    pub specs2: Specs2,                  //specs2
     pub tile_type: TileType,             //tp
-    pub repeat_layers: Option<Vec<i32>>, //repeatL
+    pub repeat_layers: Option<RepeatLayers>, //repeatL
     pub buffer_tiles: i32,               //bfTiles
     pub random_vars: Option<i32>,        //rnd
     pub preview_pos: i32,                //ptPos
     pub tags: Vec<String>,               //tags
+    /// properties tileman doesn't know, as `(key, raw lingo value)` in the order they were read
+    pub extra_props: Vec<(String, String)>,
+    /// every property's value exactly as written in the file, only filled in when parsing with
+    /// [`keep_raw_props`](lingo_de::ParseOptions::keep_raw_props). Values that still mean the same get written back verbatim.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub raw_props: BTreeMap<String, String>,
+    /// comment lines right before the tile, as written, saved back in front of it. Not part of equality.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub comments: Vec<String>,
+}
+
+/// `#repeatL` of voxel tiles: how many sublayers deep each image slice of the sprite is repeated.
+/// The png has one slice per entry, the sum is how deep the tile reaches.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct RepeatLayers(pub Vec<i32>);
+
+impl RepeatLayers {
+    /// Number of image slices the sprite needs
+    pub fn layer_count(&self) -> usize {
+        self.0.len()
+    }
+    /// Sublayers the tile covers, all repeats added up
+    pub fn depth(&self) -> i32 {
+        self.0.iter().sum()
+    }
+}
+
+impl From<Vec<i32>> for RepeatLayers {
+    fn from(layers: Vec<i32>) -> Self {
+        RepeatLayers(layers)
+    }
+}
+
+/// Geometry cells of one specs layer, in the same order lingo stores them.
+/// Big tiles with long uniform stretches can be kept as runs instead, read either through `spec_at`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpecData {
+    Flat(Vec<TileCell>),
+    RunLength(Vec<(TileCell, usize)>),
+}
+
+/// The second geometry layer. Most tiles don't have one and write `#specs2:0`, some tools leave the property out.
+#[derive(Debug, Clone, PartialEq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Specs2 {
+    /// no `#specs2` at all, it stays out when saving
+    Missing,
+    /// `#specs2:0`, or `void`
+    #[default]
+    Zero,
+    /// a real layer, kept even if every cell is the default one
+    Layer(SpecData),
+}
+
+impl Specs2 {
+    pub fn layer(&self) -> Option<&SpecData> {
+        match self {
+            Specs2::Layer(cells) => Some(cells),
+            Specs2::Missing | Specs2::Zero => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AppError {
+    TextureNotLargeEnough(String),
+    InvalidTexture(String, png::DecodingError),
+    MissingTexture(String),
+    IOError(String),
+    /// no category with this name
+    MissingCategory(String),
+    /// no tile with this name where it was looked for
+    MissingTile(String),
+    /// a tile with this name is already where it was going
+    TileNameTaken(String),
+    /// a category or folder with this name already exists
+    CategoryNameTaken(String),
+    Todo,
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct AppPersistentConfig {
     pub root_path: std::path::PathBuf,
     pub output_path: std::path::PathBuf,
+    /// how many tile previews keep their textures loaded
+    #[serde(default = "default_preview_cache_size")]
+    pub preview_cache_size: usize,
+}
+
+pub const DEFAULT_PREVIEW_CACHE_SIZE: usize = 64;
+
+fn default_preview_cache_size() -> usize {
+    DEFAULT_PREVIEW_CACHE_SIZE
 }
 
 impl PartialEq for TileInfo {
@@ -104,6 +336,7 @@ impl PartialEq for TileInfo {
             && self.random_vars == other.random_vars
             //&& self.preview_pos == other.preview_pos
             && self.tags == other.tags
+            && self.extra_props == other.extra_props
     }
 
     fn ne(&self, other: &Self) -> bool {
@@ -111,13 +344,30 @@ impl PartialEq for TileInfo {
     }
 }
 
+impl PartialEq for SpecData {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl std::hash::Hash for SpecData {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for cell in self.iter() {
+            cell.hash(state);
+        }
+    }
+}
+
+impl From<Vec<TileCell>> for SpecData {
+    fn from(cells: Vec<TileCell>) -> Self {
+        SpecData::Flat(cells)
+    }
+}
+
 impl PartialEq for TileCategory {
     fn eq(&self, other: &Self) -> bool {
-        //self.is_subfolder == other.is_subfolder
-        //&&
-        self.name == other.name
-        //&& self.color == other.color
-        //&& self.tiles == other.tiles
+        self.same_identity(other)
     }
 
     fn ne(&self, other: &Self) -> bool {
@@ -126,6 +376,46 @@ impl PartialEq for TileCategory {
 }
 
 impl TileCategory {
+    /// Whether both describe the same category, which only depends on the name.
+    /// A subfolder category and the main init header for it can disagree on color and tiles and still match.
+    pub fn same_identity(&self, other: &Self) -> bool {
+        //self.is_subfolder == other.is_subfolder
+        //&&
+        self.name == other.name
+        //&& self.color == other.color
+        //&& self.tiles == other.tiles
+    }
+    /// `Some(true)` if every tile is active, `Some(false)` if none are and `None` for a mix.
+    /// Stops at the first tile that differs, an empty category counts as none active.
+    pub fn tiles_active(&self) -> Option<bool> {
+        let mut tiles = self.tiles.iter();
+        let first = tiles.next().is_some_and(|tile| tile.active);
+        match tiles.all(|tile| tile.active == first) {
+            true => Some(first),
+            false => None,
+        }
+    }
+    /// Does nothing for a category that only lives in the main init, saving skips its inactive tiles
+    /// and there's no subfolder init to keep them in.
+    pub fn set_tiles_active(&mut self, active: bool) {
+        if self.subfolder.is_none() {
+            return;
+        }
+        for tile in self.tiles.iter_mut() {
+            tile.active = active;
+        }
+    }
+    /// Writes the category as a subfolder tileset into `dir`: an init.txt with the header and every tile,
+    /// disabled ones included, and a color.txt with `r,g,b`. Tile pngs aren't copied.
+    pub fn export_to_folder(&self, dir: &std::path::Path) -> Result<(), AppError> {
+        std::fs::create_dir_all(dir).map_err(|err| AppError::IOError(format!("{err:?}")))?;
+        let init_text = lingo_ser::serialize_category(self, false).join("\n");
+        std::fs::write(dir.join("init.txt"), init_text)
+            .map_err(|err| AppError::IOError(format!("{err:?}")))?;
+        let [r, g, b] = self.color;
+        std::fs::write(dir.join("color.txt"), format!("{r},{g},{b}"))
+            .map_err(|err| AppError::IOError(format!("{err:?}")))
+    }
     pub fn filepath(&self) -> Option<std::path::PathBuf> {
         match self.subfolder.clone() {
             Some(sub) => Some(sub.join("init.txt")),
@@ -133,6 +423,25 @@ impl TileCategory {
         }
     }
 
+    /// Black or white, whichever reads better on top of the category color
+    #[cfg(feature = "gui")]
+    pub fn text_color_for_contrast(&self) -> egui::Color32 {
+        // relative luminance as in WCAG, from linearized sRGB channels
+        let [r, g, b] = self.color.map(|channel| {
+            let channel = channel as f32 / 255.0;
+            if channel <= 0.04045 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        // past this point black text has the higher contrast ratio
+        match luminance > 0.179 {
+            true => egui::Color32::BLACK,
+            false => egui::Color32::WHITE,
+        }
+    }
     pub fn new_main(name: String, color: PrimitiveColor, index: usize) -> Self {
         TileCategory {
             enabled: true,
@@ -142,6 +451,7 @@ impl TileCategory {
             tiles: Vec::new(),
             scheduled_change: TileCategoryChange::None,
             index,
+            comments: Vec::new(),
         }
     }
     pub fn new_sub(
@@ -161,7 +471,45 @@ impl TileCategory {
             //scheduled_move_to_sub: false,
             scheduled_change: TileCategoryChange::None,
             index,
+            comments: Vec::new(),
+        }
+    }
+    /// Adds the tile from a lingo line as [`lingo_ser::serialize_tile_info`] writes it, always as a new tile.
+    /// If the name is taken the copy gets a number, `name (2)` and so on. Returns where it went.
+    pub fn paste_tile(&mut self, line: &str) -> Result<usize, DeserError> {
+        let mut tile = lingo_de::parse_tile_info(line.trim(), true)?;
+        if self.tiles.iter().any(|existing| existing.name == tile.name) {
+            tile.name = unused_tile_name(&self.tiles, &tile.name);
+        }
+        self.tiles.push(tile);
+        Ok(self.tiles.len() - 1)
+    }
+    /// Rough footprint of the category and all of its tiles
+    pub fn approx_memory_bytes(&self) -> usize {
+        std::mem::size_of::<TileCategory>()
+            + self.name.capacity()
+            + self.tiles.iter().map(|tile| tile.approx_memory_bytes()).sum::<usize>()
+            + (self.tiles.capacity() - self.tiles.len()) * std::mem::size_of::<TileInfo>()
+    }
+}
+
+/// `name (2)`, `name (3)`... whichever is free first
+fn unused_tile_name(tiles: &[TileInfo], name: &str) -> String {
+    (2..)
+        .map(|number| format!("{name} ({number})"))
+        .find(|candidate| tiles.iter().all(|tile| tile.name != *candidate))
+        .expect("ran out of numbers")
+}
+
+/// Adds `tile` to `tiles`, skipping it if an identical one is there and renaming it if only the name clashes
+fn push_tile_keeping_both(tiles: &mut Vec<TileInfo>, mut tile: TileInfo) {
+    match tiles.iter().find(|existing| existing.name == tile.name) {
+        Some(existing) if *existing == tile => {}
+        Some(_) => {
+            tile.name = unused_tile_name(tiles, &tile.name);
+            tiles.push(tile);
         }
+        None => tiles.push(tile),
     }
 }
 
@@ -178,9 +526,16 @@ macro_rules! lookup_static_cyclemap {
 }
 
 impl TileCell {
+    /// Valid numbers are `-1` (any) through `7` (entrance), and `9` (glass). `8` is unused by the editor.
+    /// Anything else is an [`DeserError::InvalidValue`].
     pub fn from_number(raw_cell: i32) -> Result<TileCell, DeserError> {
         lookup_static_cyclemap!(TILE_CELL_NUMBERS, get_left, &raw_cell)
     }
+    /// Inverse of [`from_number`](Self::from_number)
+    pub fn to_number(&self) -> i32 {
+        lookup_static_cyclemap!(TILE_CELL_NUMBERS, get_right, self)
+            .expect("Something went horribly wrong on tile cell number")
+    }
     pub fn as_number(&self) -> Result<i32, DeserError> {
         lookup_static_cyclemap!(TILE_CELL_NUMBERS, get_right, self)
     }
@@ -197,19 +552,183 @@ impl TileCell {
 }
 
 impl TileType {
+    pub const ALL: [TileType; 6] = [
+        TileType::VoxelStruct,
+        TileType::VoxelStructRockType,
+        TileType::VoxelStructDisplaceV,
+        TileType::VoxelStructDisplaceH,
+        TileType::VoxelStructSandType,
+        TileType::Box,
+    ];
     pub fn from_string<'a>(text: &'a str) -> Result<TileType, DeserError> {
         lookup_static_cyclemap!(TILE_TYPE_STRINGS, get_left, &text)
+            .map_err(|_| DeserError::InvalidValue(format!("unknown tile type {text:?}")))
     }
     pub fn as_string<'a>(&self) -> Result<&'a str, DeserError> {
         lookup_static_cyclemap!(TILE_TYPE_STRINGS, get_right, self)
     }
+    /// First game version whose editor knows this type
+    pub fn min_game_version(&self) -> GameVersion {
+        match self {
+            TileType::VoxelStructSandType => GameVersion::Drought,
+            _ => GameVersion::Vanilla,
+        }
+    }
+}
+
+/// The `#tp` string, as [`TileType::from_string`] reads it
+impl std::fmt::Display for TileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(
+            self.as_string()
+                .expect("Something went horribly wrong on tile type string"),
+        )
+    }
+}
+
+/// One line summary like `name (2x3, voxelStruct, 1 tag)` for lists and logs, `?` stands in for a missing size
+impl std::fmt::Display for TileInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dimension = |value: Option<i32>| value.map_or("?".to_string(), |value| value.to_string());
+        write!(
+            f,
+            "{} ({}x{}, {}, {} {})",
+            self.name,
+            dimension(self.width()),
+            dimension(self.height()),
+            self.tile_type,
+            self.tags.len(),
+            match self.tags.len() {
+                1 => "tag",
+                _ => "tags",
+            }
+        )
+    }
+}
+
+impl SpecData {
+    pub fn spec_at(&self, index: usize) -> Option<TileCell> {
+        match self {
+            SpecData::Flat(cells) => cells.get(index).copied(),
+            SpecData::RunLength(runs) => {
+                let mut start = 0;
+                for (cell, count) in runs.iter() {
+                    if index < start + count {
+                        return Some(*cell);
+                    }
+                    start += count;
+                }
+                None
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            SpecData::Flat(cells) => cells.len(),
+            SpecData::RunLength(runs) => runs.iter().map(|(_, count)| count).sum(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = TileCell> + '_> {
+        match self {
+            SpecData::Flat(cells) => Box::new(cells.iter().copied()),
+            SpecData::RunLength(runs) => Box::new(
+                runs.iter()
+                    .flat_map(|(cell, count)| std::iter::repeat_n(*cell, *count)),
+            ),
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<TileCell> {
+        self.iter().collect()
+    }
+
+    /// Same cells, stored as runs
+    pub fn compressed(&self) -> SpecData {
+        let mut runs: Vec<(TileCell, usize)> = Vec::new();
+        for cell in self.iter() {
+            match runs.last_mut() {
+                Some((last, count)) if *last == cell => *count += 1,
+                _ => runs.push((cell, 1)),
+            }
+        }
+        runs.shrink_to_fit();
+        SpecData::RunLength(runs)
+    }
+
+    /// Same cells, stored one entry per cell
+    pub fn flattened(&self) -> SpecData {
+        SpecData::Flat(self.to_vec())
+    }
+
+    /// Rough heap footprint of the cell storage
+    pub fn approx_memory_bytes(&self) -> usize {
+        match self {
+            SpecData::Flat(cells) => cells.capacity() * std::mem::size_of::<TileCell>(),
+            SpecData::RunLength(runs) => {
+                runs.capacity() * std::mem::size_of::<(TileCell, usize)>()
+            }
+        }
+    }
 }
 
 impl TileInfo {
+    /// Adds `tag` with surrounding whitespace trimmed. Returns false and changes nothing when it's empty or
+    /// the tile already has it in any casing.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim();
+        if tag.is_empty() || self.tags.iter().any(|existing| existing.to_lowercase() == tag.to_lowercase()) {
+            return false;
+        }
+        self.tags.push(tag.to_string());
+        true
+    }
+    /// Removes `tag` exactly as written, returns whether the tile had it
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let before = self.tags.len();
+        self.tags.retain(|existing| existing != tag);
+        self.tags.len() != before
+    }
+    /// Names of the fields that differ from `other`, leaving out `name` and the [raw values](Self::raw_props)
+    pub fn changed_fields(&self, other: &TileInfo) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        macro_rules! compare {
+            ($($field:ident),*) => {
+                $(
+                    if self.$field != other.$field {
+                        fields.push(stringify!($field));
+                    }
+                )*
+            };
+        }
+        compare!(
+            active,
+            size,
+            specs,
+            specs2,
+            tile_type,
+            repeat_layers,
+            buffer_tiles,
+            random_vars,
+            preview_pos,
+            tags,
+            extra_props
+        );
+        fields
+    }
+    /// Starts a [`TileInfoBuilder`] for a tile called `name`
+    pub fn builder(name: impl Into<String>) -> TileInfoBuilder {
+        TileInfoBuilder::new(name)
+    }
     pub fn display_cells(&self, take_specs2: bool) -> multiarray::Array2D<TileCell> {
         let selected_specs = match take_specs2 {
             false => Some(self.specs.clone()),
-            true => self.specs2.clone(),
+            true => self.specs2.layer().cloned(),
         };
         if let Some(actual_specs) = selected_specs {
             let xmax = *self.size.get(0).unwrap_or(&1);
@@ -219,9 +738,9 @@ impl TileInfo {
             for y in 0..ymax {
                 for x in 0..xmax {
                     let index = ((xmax * ymax) - (y + x * ymax + 1)) as usize;
-                    let cell = actual_specs.get(index).unwrap_or(&TileCell::Any);
+                    let cell = actual_specs.spec_at(index).unwrap_or(TileCell::Any);
                     //let display = cell.display_str();
-                    res[[x as usize, y as usize]] = cell.clone();
+                    res[[x as usize, y as usize]] = cell;
                 }
                 //res.push('\n')
             }
@@ -229,21 +748,921 @@ impl TileInfo {
         };
         multiarray::Array2D::new([0, 0], TileCell::Any)
     }
+
+    /// One pixel per cell of `specs` (or `specs2`), colored by [`TileCell::display_color`], laid out like
+    /// [`TileInfo::display_cells`]. Cells the specs don't cover, and any specs past the declared size
+    /// (drawn as extra rows below), get [`Thumbnail::ERROR_COLORS`] in a checkerboard.
+    pub fn preview_thumbnail(&self, take_specs2: bool) -> Thumbnail {
+        let selected_specs = match take_specs2 {
+            false => Some(&self.specs),
+            true => self.specs2.layer(),
+        };
+        let Some(specs) = selected_specs else {
+            return Thumbnail::default();
+        };
+        let declared = |axis: usize| self.size.get(axis).map_or(0, |len| (*len).max(0) as usize);
+        let (width, declared_height) = (declared(0), declared(1));
+        if width == 0 || declared_height == 0 {
+            return Thumbnail::error(1, 1);
+        }
+        let declared_cells = width * declared_height;
+        let extra_rows = specs.len().saturating_sub(declared_cells).div_ceil(width);
+        let mut thumbnail = Thumbnail::error(width, declared_height + extra_rows);
+        thumbnail.mismatched = specs.len() != declared_cells;
+        for y in 0..declared_height {
+            for x in 0..width {
+                let index = declared_cells - (y + x * declared_height + 1);
+                if let Some(cell) = specs.spec_at(index) {
+                    let [r, g, b] = cell.display_color();
+                    thumbnail.pixels[y * width + x] = [r, g, b, 255];
+                }
+            }
+        }
+        thumbnail
+    }
+
+    /// Checks the tile is something the editor can actually place
+    pub fn validate(&self) -> Result<(), DeserError> {
+        if self.name.trim().is_empty() {
+            return Err(DeserError::InvalidValue(String::from("tile has no name")));
+        }
+        if self.name.contains('"') {
+            return Err(DeserError::InvalidValue(format!(
+                "tile name {:?} contains a quote",
+                self.name
+            )));
+        }
+        let (width, height) = match (self.size.first(), self.size.get(1)) {
+            (Some(width), Some(height)) => (*width, *height),
+            _ => {
+                return Err(DeserError::InvalidValue(format!(
+                    "size {:?} needs a width and a height",
+                    self.size
+                )))
+            }
+        };
+        if width <= 0 || height <= 0 {
+            return Err(DeserError::InvalidValue(format!(
+                "size {width}x{height} has no area"
+            )));
+        }
+        // experimental 3d voxel tiles add a depth, nothing else takes more than two
+        match (self.depth(), self.tile_type) {
+            (None, _) => {}
+            (Some(_), TileType::Box) => {
+                return Err(DeserError::InvalidValue(String::from(
+                    "box tiles can't have a depth",
+                )))
+            }
+            (Some(depth), _) if depth <= 0 => {
+                return Err(DeserError::InvalidValue(format!("depth {depth} isn't positive")))
+            }
+            (Some(_), _) if self.size.len() > 3 => {
+                return Err(DeserError::InvalidValue(format!(
+                    "size {:?} has more than three components",
+                    self.size
+                )))
+            }
+            _ => {}
+        }
+        let expected_cells = (width * height) as usize;
+        // empty specs are inert tiles without collision
+        if !self.specs.is_empty() && self.specs.len() != expected_cells {
+            return Err(DeserError::InvalidValue(format!(
+                "specs has {} cells but size {width}x{height} needs {expected_cells}",
+                self.specs.len()
+            )));
+        }
+        if let Some(specs2) = self.specs2.layer() {
+            if specs2.len() != expected_cells {
+                return Err(DeserError::InvalidValue(format!(
+                    "specs2 has {} cells but size {width}x{height} needs {expected_cells}",
+                    specs2.len()
+                )));
+            }
+        }
+        // rock and sand types ignore repeatL, old saves wrote a placeholder [0] for them
+        let uses_layers = matches!(
+            self.tile_type,
+            TileType::VoxelStruct | TileType::VoxelStructDisplaceV | TileType::VoxelStructDisplaceH
+        );
+        if uses_layers {
+            let Some(RepeatLayers(repeat_layers)) = &self.repeat_layers else {
+                return Err(DeserError::InvalidValue(format!(
+                    "{} needs repeatL, one entry per layer image",
+                    self.tile_type
+                )));
+            };
+            if repeat_layers.is_empty() {
+                return Err(DeserError::InvalidValue(format!(
+                    "{} has an empty repeatL, it needs at least one layer image",
+                    self.tile_type
+                )));
+            }
+            if repeat_layers.iter().any(|layers| *layers <= 0) {
+                return Err(DeserError::InvalidValue(format!(
+                    "repeatL {repeat_layers:?} has a layer that isn't positive"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn width(&self) -> Option<i32> {
+        self.size.first().copied()
+    }
+    pub fn height(&self) -> Option<i32> {
+        self.size.get(1).copied()
+    }
+    /// Third `#sz` component of experimental 3d voxel tiles, `None` for regular ones
+    pub fn depth(&self) -> Option<i32> {
+        self.size.get(2).copied()
+    }
+
+    /// Pulls `preview_pos` back into `0..=width*height`, call after changing `size`
+    pub fn clamp_preview_pos(&mut self) {
+        let cells = self.width().unwrap_or(0).max(0) * self.height().unwrap_or(0).max(0);
+        self.preview_pos = self.preview_pos.clamp(0, cells);
+    }
+
+    /// Switches both specs layers to run length storage, worth it for big uniform tiles
+    pub fn compact_specs(&mut self) {
+        self.specs = self.specs.compressed();
+        if let Specs2::Layer(specs2) = &mut self.specs2 {
+            *specs2 = specs2.compressed();
+        }
+    }
+
+    /// Rough footprint of the tile, heap data included
+    pub fn approx_memory_bytes(&self) -> usize {
+        std::mem::size_of::<TileInfo>()
+            + self.name.capacity()
+            + self.size.capacity() * std::mem::size_of::<i32>()
+            + self.specs.approx_memory_bytes()
+            + self.specs2.layer().map_or(0, |specs2| specs2.approx_memory_bytes())
+            + self.repeat_layers.as_ref().map_or(0, |layers| layers.0.capacity() * std::mem::size_of::<i32>())
+            + self.tags.iter().map(|tag| std::mem::size_of::<String>() + tag.capacity()).sum::<usize>()
+            + self
+                .extra_props
+                .iter()
+                .map(|(key, value)| std::mem::size_of::<(String, String)>() + key.capacity() + value.capacity())
+                .sum::<usize>()
+            + self
+                .raw_props
+                .iter()
+                .map(|(key, value)| std::mem::size_of::<(String, String)>() + key.capacity() + value.capacity())
+                .sum::<usize>()
+    }
+
+    /// How many graphical variants the tile has, a missing `#rnd` means just the one
+    pub fn variant_count(&self) -> i32 {
+        self.random_vars.unwrap_or(1).max(1)
+    }
+
+    /// Picks a variant in `0..variant_count` that only depends on `seed`, so previews are reproducible.
+    /// This is a splitmix64 step rather than director's rng, so it won't pick what the game would.
+    pub fn variant_for_seed(&self, seed: u64) -> i32 {
+        let mut mixed = seed.wrapping_add(0x9E3779B97F4A7C15);
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94D049BB133111EB);
+        mixed ^= mixed >> 31;
+        (mixed % self.variant_count() as u64) as i32
+    }
+
+    /// Where the editor expects this tile's png to be inside `dir`
+    pub fn image_path(&self, dir: &std::path::Path) -> std::path::PathBuf {
+        dir.join(format!("{}.png", self.name))
+    }
+
+    /// Rough pixel size of the tile's sprite sheet: every variant side by side,
+    /// one image per repeat layer stacked below each other, buffer tiles included.
+    /// Ignores the editor preview strip, so real pngs run a bit taller.
+    pub fn sprite_sheet_size(&self, cell_px: u32) -> (i32, i32) {
+        let cell_px = cell_px as i32;
+        let width = self.size.first().copied().unwrap_or(0).max(0);
+        let height = self.size.get(1).copied().unwrap_or(0).max(0);
+        let layer_images = match (&self.tile_type, &self.repeat_layers) {
+            (
+                TileType::VoxelStruct
+                | TileType::VoxelStructDisplaceV
+                | TileType::VoxelStructDisplaceH,
+                Some(repeat_layers),
+            ) => repeat_layers.layer_count().max(1) as i32,
+            _ => 1,
+        };
+        (
+            (width + 2 * self.buffer_tiles) * cell_px * self.variant_count(),
+            (height + 2 * self.buffer_tiles) * cell_px * layer_images,
+        )
+    }
+}
+
+/// Chained construction of a [`TileInfo`] for tools and tests, checked by [`TileInfo::validate`] on [`build`](Self::build).
+/// Starts as an active 1x1 voxelStruct without collision, tags or specs2.
+/// Voxel types that never got `repeat_layers` get a single layer, like the editor writes by default.
+#[derive(Debug, Clone)]
+pub struct TileInfoBuilder {
+    tile: TileInfo,
+}
+
+impl TileInfoBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            tile: TileInfo {
+                active: true,
+                name: name.into(),
+                size: vec![1, 1],
+                specs: SpecData::from(Vec::new()),
+                specs2: Specs2::Zero,
+                tile_type: TileType::VoxelStruct,
+                repeat_layers: None,
+                buffer_tiles: 0,
+                random_vars: Some(1),
+                preview_pos: 0,
+                tags: Vec::new(),
+                extra_props: Vec::new(),
+                raw_props: BTreeMap::new(),
+                comments: Vec::new(),
+            },
+        }
+    }
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.tile.name = name.into();
+        self
+    }
+    pub fn size(mut self, width: i32, height: i32) -> Self {
+        self.tile.size = vec![width, height];
+        self
+    }
+    pub fn specs(mut self, cells: Vec<TileCell>) -> Self {
+        self.tile.specs = cells.into();
+        self
+    }
+    pub fn specs2(mut self, cells: Vec<TileCell>) -> Self {
+        self.tile.specs2 = Specs2::Layer(cells.into());
+        self
+    }
+    pub fn tile_type(mut self, tile_type: TileType) -> Self {
+        self.tile.tile_type = tile_type;
+        self
+    }
+    pub fn repeat_layers(mut self, repeat_layers: Vec<i32>) -> Self {
+        self.tile.repeat_layers = Some(repeat_layers.into());
+        self
+    }
+    pub fn buffer_tiles(mut self, buffer_tiles: i32) -> Self {
+        self.tile.buffer_tiles = buffer_tiles;
+        self
+    }
+    pub fn random_vars(mut self, random_vars: i32) -> Self {
+        self.tile.random_vars = Some(random_vars);
+        self
+    }
+    pub fn preview_pos(mut self, preview_pos: i32) -> Self {
+        self.tile.preview_pos = preview_pos;
+        self
+    }
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tile.tags = tags;
+        self
+    }
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tile.tags.push(tag.into());
+        self
+    }
+    pub fn active(mut self, active: bool) -> Self {
+        self.tile.active = active;
+        self
+    }
+    pub fn build(mut self) -> Result<TileInfo, DeserError> {
+        if self.tile.tile_type != TileType::Box && self.tile.repeat_layers.is_none() {
+            self.tile.repeat_layers = Some(vec![1].into());
+        }
+        self.tile.validate()?;
+        Ok(self.tile)
+    }
 }
 
+
 impl TileInit {
     pub fn main_init_path(&self) -> std::path::PathBuf {
         self.root.join("init.txt")
     }
+    /// Sizing hint for packing every tile into one atlas, see [`TileInfo::sprite_sheet_size`].
+    /// Returns the summed sprite sheet area and the largest width and height any single sheet needs.
+    pub fn atlas_estimate(&self, cell_px: u32) -> (u32, (i32, i32)) {
+        let mut total_area = 0u32;
+        let mut max_tile = (0, 0);
+        for (_, tile) in self.iter_tiles() {
+            let (width, height) = tile.sprite_sheet_size(cell_px);
+            let area = (width.max(0) as u32) * (height.max(0) as u32);
+            total_area = total_area.saturating_add(area);
+            max_tile = (max_tile.0.max(width), max_tile.1.max(height));
+        }
+        (total_area, max_tile)
+    }
+    /// Tiles whose sprite sheet is wider or taller than `max_px`, with the size it would need
+    pub fn oversized_sprites(&self, max_px: u32, cell_px: u32) -> Vec<(String, (i32, i32))> {
+        self.iter_tiles()
+            .map(|(_, tile)| (tile.name.clone(), tile.sprite_sheet_size(cell_px)))
+            .filter(|(_, (width, height))| (*width).max(*height) as i64 > max_px as i64)
+            .collect()
+    }
+    /// `(tile, tag)` for every tag missing from `known`, to catch typos the game silently ignores
+    pub fn unknown_tags(&self, known: &[&str]) -> Vec<(String, String)> {
+        self.iter_tiles()
+            .flat_map(|(_, tile)| {
+                tile.tags
+                    .iter()
+                    .filter(|tag| !known.contains(&tag.as_str()))
+                    .map(move |tag| (tile.name.clone(), tag.clone()))
+            })
+            .collect()
+    }
+    /// Names and [approximate sizes](TileCategory::approx_memory_bytes) of the `n` biggest categories, biggest first
+    pub fn heaviest_categories(&self, n: usize) -> Vec<(String, usize)> {
+        let mut weights: Vec<(String, usize)> = self
+            .categories
+            .iter()
+            .map(|category| (category.name.clone(), category.approx_memory_bytes()))
+            .collect();
+        weights.sort_by(|(_, weight1), (_, weight2)| weight2.cmp(weight1));
+        weights.truncate(n);
+        weights
+    }
+    /// Every tile name used more than once, with the category of each occurrence.
+    /// The game's editor breaks on these. Names are in the order they first show up.
+    pub fn find_duplicate_names(&self) -> Vec<(String, Vec<&TileCategory>)> {
+        let mut seen: Vec<(String, Vec<&TileCategory>)> = Vec::new();
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for (category, tile) in self.iter_tiles() {
+            match positions.get(tile.name.as_str()) {
+                Some(position) => seen[*position].1.push(category),
+                None => {
+                    positions.insert(tile.name.as_str(), seen.len());
+                    seen.push((tile.name.clone(), vec![category]));
+                }
+            }
+        }
+        seen.retain(|(_, categories)| categories.len() > 1);
+        seen
+    }
+    /// Tile types used anywhere in the pack, in [`TileType::ALL`] order
+    pub fn used_tile_types(&self) -> Vec<TileType> {
+        TileType::ALL
+            .into_iter()
+            .filter(|tile_type| self.iter_tiles().any(|(_, tile)| tile.tile_type == *tile_type))
+            .collect()
+    }
+    /// Oldest game version that can load every tile in the pack.
+    /// Only tile types differ between versions so far, all cells and tags work in vanilla.
+    pub fn min_game_version(&self) -> GameVersion {
+        self.used_tile_types()
+            .iter()
+            .map(TileType::min_game_version)
+            .max()
+            .unwrap_or(GameVersion::Vanilla)
+    }
+    /// Every tile with the category it's in, categories in order
+    pub fn iter_tiles(&self) -> impl Iterator<Item = (&TileCategory, &TileInfo)> {
+        self.categories
+            .iter()
+            .flat_map(|category| category.tiles.iter().map(move |tile| (category, tile)))
+    }
+    /// Every tile with the position of its category in `categories`, for editing in place
+    pub fn iter_tiles_mut(&mut self) -> impl Iterator<Item = (usize, &mut TileInfo)> {
+        self.categories
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(category_index, category)| {
+                category.tiles.iter_mut().map(move |tile| (category_index, tile))
+            })
+    }
+    pub fn tile_count(&self) -> usize {
+        self.categories.iter().map(|category| category.tiles.len()).sum()
+    }
+    /// Tiles whose name fuzzily matches `query`, best first, see [`utl::fuzzy_score`].
+    /// Equal scores keep the pack order, an empty query matches every tile with a score of 0.
+    pub fn fuzzy_find(&self, query: &str) -> Vec<(&TileInfo, i64)> {
+        let mut found: Vec<(&TileInfo, i64)> = self
+            .iter_tiles()
+            .filter_map(|(_, tile)| Some((tile, fuzzy_score(query, &tile.name)?)))
+            .collect();
+        found.sort_by(|(_, score1), (_, score2)| score2.cmp(score1));
+        found
+    }
+    pub fn stats(&self) -> TilesetStats {
+        let mut tiles_per_type = HashMap::new();
+        for (_, tile) in self.iter_tiles() {
+            *tiles_per_type.entry(tile.tile_type).or_insert(0) += 1;
+        }
+        TilesetStats {
+            categories: self.categories.len(),
+            disabled_categories: self.categories.iter().filter(|category| !category.enabled).count(),
+            tiles: self.tile_count(),
+            tiles_per_type,
+            errored_lines: self.errored_lines.len(),
+        }
+    }
+    /// Copy of a single category, with the same root, to edit on its own and [`merge`](Self::merge) back
+    pub fn isolate_category(&self, name: &str) -> Option<TileInit> {
+        let category = self.find_category(name)?;
+        Some(TileInit {
+            root: self.root.clone(),
+            categories: vec![category.clone()],
+            errored_lines: Vec::new(),
+            trailing_comments: Vec::new(),
+        })
+    }
+    /// Folds `other` into this init. Categories and tiles are matched by name,
+    /// `strategy` picks what happens on a match. Unmatched ones get appended.
+    pub fn merge(&mut self, other: TileInit, strategy: MergeStrategy) {
+        for other_category in other.categories {
+            match self
+                .categories
+                .iter_mut()
+                .find(|category| category.name == other_category.name)
+            {
+                Some(category) => {
+                    if strategy == MergeStrategy::PreferOther {
+                        category.color = other_category.color;
+                        category.enabled = other_category.enabled;
+                        category.scheduled_change = other_category.scheduled_change;
+                    }
+                    for other_tile in other_category.tiles {
+                        match (
+                            category.tiles.iter_mut().find(|tile| tile.name == other_tile.name),
+                            strategy,
+                        ) {
+                            (None, _) => category.tiles.push(other_tile),
+                            (Some(tile), MergeStrategy::PreferOther) => *tile = other_tile,
+                            (Some(_), MergeStrategy::PreferSelf) => {}
+                            (Some(_), MergeStrategy::KeepBoth) => {
+                                push_tile_keeping_both(&mut category.tiles, other_tile)
+                            }
+                        }
+                    }
+                }
+                None => {
+                    let mut new_category = other_category;
+                    //after the last one, whatever index the pack starts counting from
+                    new_category.index = self
+                        .categories
+                        .iter()
+                        .map(|category| category.index + 1)
+                        .max()
+                        .unwrap_or(new_category.index);
+                    self.categories.push(new_category);
+                }
+            }
+        }
+        self.errored_lines.extend(other.errored_lines);
+    }
+    /// Renames every category whose name is a key in `mapping` and returns how many were renamed.
+    /// Categories that end up with the same name are then folded together with [`merge_duplicate_categories`](Self::merge_duplicate_categories).
+    /// Subfolder categories keep their folder, only the name written to the main init changes.
+    pub fn rename_categories(&mut self, mapping: &[(String, String)]) -> usize {
+        let mut renamed = 0;
+        for category in self.categories.iter_mut() {
+            if let Some((_, new_name)) = mapping.iter().find(|(old_name, _)| *old_name == category.name) {
+                category.name = new_name.clone();
+                renamed += 1;
+            }
+        }
+        if renamed > 0 {
+            self.merge_duplicate_categories();
+        }
+        renamed
+    }
+    /// Renames one category, see [`rename_category_with`](Self::rename_category_with).
+    /// A subfolder category keeps its folder, so renaming the folder too is up to the caller.
+    pub fn rename_category(&mut self, old: &str, new: &str) -> Result<(), AppError> {
+        self.rename_category_with(old, new, false)
+    }
+    /// Renames one category. Unlike [`rename_categories`](Self::rename_categories) this never merges:
+    /// if a category named `new` already exists nothing changes and it's an [`AppError::CategoryNameTaken`].
+    /// With `rename_folder` a subfolder category's folder is renamed on disk to match, which fails the same way
+    /// when a folder of that name is already there.
+    pub fn rename_category_with(&mut self, old: &str, new: &str, rename_folder: bool) -> Result<(), AppError> {
+        if old == new {
+            return self
+                .find_category(old)
+                .map(|_| ())
+                .ok_or_else(|| AppError::MissingCategory(old.to_string()));
+        }
+        if self.find_category(new).is_some() {
+            return Err(AppError::CategoryNameTaken(new.to_string()));
+        }
+        let category = self
+            .find_category_mut(old)
+            .ok_or_else(|| AppError::MissingCategory(old.to_string()))?;
+        if let (true, Some(subfolder)) = (rename_folder, &category.subfolder) {
+            let renamed = subfolder.with_file_name(new);
+            if renamed.exists() {
+                return Err(AppError::CategoryNameTaken(renamed.display().to_string()));
+            }
+            std::fs::rename(subfolder, &renamed).map_err(|err| AppError::IOError(format!("{err:?}")))?;
+            log::info!("renamed {} to {}", subfolder.display(), renamed.display());
+            category.subfolder = Some(renamed);
+        }
+        category.name = new.to_string();
+        Ok(())
+    }
+    /// Takes the tile named `tile_name` out of category `from` and appends it to category `to`.
+    /// Only the init changes, a tile image in a subfolder stays where it is.
+    /// If `to` already has a tile of that name nothing moves and it's an [`AppError::TileNameTaken`],
+    /// renaming isn't an option since the name is also the image's. Moving a tile to its own category does nothing.
+    pub fn move_tile(&mut self, tile_name: &str, from: &str, to: &str) -> Result<(), AppError> {
+        let from_index = self
+            .categories
+            .iter()
+            .position(|category| category.name == from)
+            .ok_or_else(|| AppError::MissingCategory(from.to_string()))?;
+        let to_index = self
+            .categories
+            .iter()
+            .position(|category| category.name == to)
+            .ok_or_else(|| AppError::MissingCategory(to.to_string()))?;
+        let tile_index = self.categories[from_index]
+            .tiles
+            .iter()
+            .position(|tile| tile.name == tile_name)
+            .ok_or_else(|| AppError::MissingTile(tile_name.to_string()))?;
+        if from_index == to_index {
+            return Ok(());
+        }
+        if self.categories[to_index].tiles.iter().any(|tile| tile.name == tile_name) {
+            return Err(AppError::TileNameTaken(tile_name.to_string()));
+        }
+        let tile = self.categories[from_index].tiles.remove(tile_index);
+        log::debug!("moving {tile_name} from {from} to {to}");
+        self.categories[to_index].tiles.push(tile);
+        Ok(())
+    }
+    /// Folds every category into the first one with the same name and returns how many were folded away.
+    /// Tiles are appended in order, like [`merge`](Self::merge) with [`MergeStrategy::KeepBoth`].
+    pub fn merge_duplicate_categories(&mut self) -> usize {
+        let mut kept: Vec<TileCategory> = Vec::with_capacity(self.categories.len());
+        let mut folded = 0;
+        for category in std::mem::take(&mut self.categories) {
+            match kept.iter_mut().find(|existing| existing.name == category.name) {
+                Some(existing) => {
+                    for tile in category.tiles {
+                        push_tile_keeping_both(&mut existing.tiles, tile);
+                    }
+                    folded += 1;
+                }
+                None => kept.push(category),
+            }
+        }
+        self.categories = kept;
+        folded
+    }
+    /// Recolors categories by name. Categories missing from the palette keep their color,
+    /// palette entries that match no category are returned.
+    pub fn apply_palette(&mut self, palette: &[(String, [u8; 3])]) -> Vec<String> {
+        let mut unmatched = Vec::new();
+        for (name, color) in palette {
+            let mut matched = false;
+            for category in self.categories.iter_mut().filter(|category| &category.name == name) {
+                category.color = *color;
+                matched = true;
+            }
+            if !matched {
+                unmatched.push(name.clone());
+            }
+        }
+        unmatched
+    }
+    /// Writes what the main init.txt would contain, category by category
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        lingo_ser::write_main_init(self, writer)
+    }
+    pub fn write_to_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        use std::io::Write;
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+    /// The whole pack as indented JSON, read back with [`from_json`](Self::from_json). Unlike the lingo
+    /// serializer this keeps everything, including errored lines and disabled tiles.
+    ///
+    /// Keys are the Rust field names and won't change between releases: the top level has `root`,
+    /// `categories`, `errored_lines` and `trailing_comments`. Categories have `index`, `enabled`, `subfolder`,
+    /// `name`, `color`, `tiles`, `scheduled_change` and `comments`. Tiles have `active`, `name`, `size`, `specs`,
+    /// `specs2`, `tile_type`, `repeat_layers`, `buffer_tiles`, `random_vars`, `preview_pos`, `tags`,
+    /// `extra_props`, `raw_props` and `comments`.
+    /// Enums are written as their variant names, so cells look like `"Wall"` and specs like `{"Flat": [...]}`.
+    #[cfg(feature = "serde")]
+    pub fn to_json_pretty(&self) -> Result<String, AppError> {
+        serde_json::to_string_pretty(self).map_err(|err| AppError::IOError(format!("{err:?}")))
+    }
+    /// Reads a pack written by [`to_json_pretty`](Self::to_json_pretty)
+    #[cfg(feature = "serde")]
+    pub fn from_json(text: &str) -> Result<TileInit, AppError> {
+        serde_json::from_str(text).map_err(|err| AppError::IOError(format!("{err:?}")))
+    }
+    /// One row per tile for spreadsheets, with the columns `category,name,type,width,height,tags,active`.
+    /// Tags share one cell separated by `;`. Cells with commas, quotes or line breaks are quoted like RFC 4180 asks.
+    pub fn to_csv(&self) -> String {
+        fn cell(text: &str) -> String {
+            match text.contains([',', '"', '\n', '\r']) {
+                true => format!("\"{}\"", text.replace('"', "\"\"")),
+                false => text.to_string(),
+            }
+        }
+        let mut csv = String::from("category,name,type,width,height,tags,active\n");
+        for (category, tile) in self.iter_tiles() {
+            let row = [
+                cell(&category.name),
+                cell(&tile.name),
+                tile.tile_type.to_string(),
+                tile.width().unwrap_or(0).to_string(),
+                tile.height().unwrap_or(0).to_string(),
+                cell(&tile.tags.join(";")),
+                tile.active.to_string(),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+    /// Writes the main init and every subfolder init under `root`.
+    /// Subfolder categories keep all their tiles in their own init, the main init only lists the enabled ones.
+    /// Problems that don't stop the main init from being written (like missing pngs) are only logged.
+    pub fn save(&self) -> Result<(), AppError> {
+        match lingo_ser::rewrite_init(self, self.root.clone()) {
+            Ok(errors) => {
+                if !errors.is_empty() {
+                    log::warn!("saved with errors: {errors:#?}");
+                }
+                Ok(())
+            }
+            Err((err, errors)) => {
+                log::error!("could not save main init: {err:?}, other errors: {errors:#?}");
+                Err(AppError::IOError(format!("{err:?}")))
+            }
+        }
+    }
+    /// Packs the tileset into a single zip for distribution, pngs are read from `src_root`.
+    /// Missing pngs are only logged, same as with [`save`](Self::save).
+    #[cfg(feature = "zip")]
+    pub fn export_zip(
+        &self,
+        src_root: &std::path::Path,
+        dest_zip: &std::path::Path,
+    ) -> Result<(), AppError> {
+        let errors = lingo_ser::export_zip(self, src_root, dest_zip)?;
+        if !errors.is_empty() {
+            log::warn!("exported with errors: {errors:#?}");
+        }
+        Ok(())
+    }
+    /// Runs every check that should pass before writing the pack to disk.
+    /// Colors need no check here, `[u8; 3]` can't hold anything the editor would reject.
+    /// Empty categories are only logged, see [`validate_for_save_with`](Self::validate_for_save_with).
+    pub fn validate_for_save(&self) -> Result<(), Vec<String>> {
+        self.validate_for_save_with(false)
+    }
+    /// [`validate_for_save`](Self::validate_for_save), with empty categories counting as problems if `reject_empty_categories` is set
+    pub fn validate_for_save_with(&self, reject_empty_categories: bool) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        let mut seen_indices = std::collections::HashMap::new();
+        for category in self.categories.iter() {
+            if category.name.trim().is_empty() {
+                problems.push(String::from("a category has no name"));
+            }
+            if category.tiles.is_empty() {
+                match reject_empty_categories {
+                    true => problems.push(format!("category {:?} has no tiles", category.name)),
+                    false => log::warn!("category {:?} has no tiles", category.name),
+                }
+            }
+            if category.name.contains('"') {
+                problems.push(format!("category name {:?} contains a quote", category.name));
+            }
+            if let Some(other) = seen_indices.insert(category.index, category.name.clone()) {
+                problems.push(format!(
+                    "categories {:?} and {:?} share index {}",
+                    other, category.name, category.index
+                ));
+            }
+            for tile in category.tiles.iter() {
+                if let Err(err) = tile.validate() {
+                    problems.push(format!("{} / {}: {err:?}", category.name, tile.name));
+                }
+            }
+        }
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems),
+        }
+    }
+    /// Drops categories without tiles and closes the gaps in the indices, still counting from the lowest index the pack had.
+    /// Returns how many went away.
+    pub fn remove_empty_categories(&mut self) -> usize {
+        let start_index = self.lowest_category_index();
+        let removed = self.prune_empty_categories_with(false);
+        self.sort_and_normalize_categories_from(start_index);
+        removed
+    }
+    /// Index the pack counts categories from, see [`category_start_index`](lingo_de::ParseOptions::category_start_index)
+    pub fn lowest_category_index(&self) -> usize {
+        self.categories.iter().map(|category| category.index).min().unwrap_or(0)
+    }
+    /// Drops categories without tiles, except subfolder ones: an empty subfolder is still a category the editor shows.
+    /// Indices are left alone. Returns how many went away.
+    pub fn prune_empty_categories(&mut self) -> usize {
+        self.prune_empty_categories_with(true)
+    }
+    /// [`prune_empty_categories`](Self::prune_empty_categories), dropping empty subfolder categories too unless `keep_subfolders`
+    pub fn prune_empty_categories_with(&mut self, keep_subfolders: bool) -> usize {
+        let before = self.categories.len();
+        self.categories.retain(|category| {
+            !category.tiles.is_empty() || (keep_subfolders && category.subfolder.is_some())
+        });
+        before - self.categories.len()
+    }
+    /// Maps a position in the flat list of all tiles (categories in order) to `(category, tile)` indices
+    pub fn locate(&self, flat_index: usize) -> Option<(usize, usize)> {
+        let mut remaining = flat_index;
+        for (category_index, category) in self.categories.iter().enumerate() {
+            if remaining < category.tiles.len() {
+                return Some((category_index, remaining));
+            }
+            remaining -= category.tiles.len();
+        }
+        None
+    }
+    /// What it takes to get from `self` to `other`, in the order of `self` for removals and `other` for everything else
+    pub fn diff(&self, other: &TileInit) -> TilesetDiff {
+        let mut diff = TilesetDiff::default();
+        for category in self.categories.iter() {
+            if other.find_category(&category.name).is_none() {
+                diff.removed_categories.push(category.name.clone());
+            }
+        }
+        for other_category in other.categories.iter() {
+            let Some(category) = self.find_category(&other_category.name) else {
+                diff.added_categories.push(other_category.name.clone());
+                continue;
+            };
+            let mut fields = Vec::new();
+            if category.color != other_category.color {
+                fields.push("color");
+            }
+            if category.enabled != other_category.enabled {
+                fields.push("enabled");
+            }
+            if category.subfolder != other_category.subfolder {
+                fields.push("subfolder");
+            }
+            if !fields.is_empty() {
+                diff.modified_categories.push(CategoryChange {
+                    name: category.name.clone(),
+                    fields,
+                });
+            }
+            for tile in category.tiles.iter() {
+                if !other_category.tiles.iter().any(|other_tile| other_tile.name == tile.name) {
+                    diff.removed_tiles.push((category.name.clone(), tile.name.clone()));
+                }
+            }
+            for other_tile in other_category.tiles.iter() {
+                match category.tiles.iter().find(|tile| tile.name == other_tile.name) {
+                    None => diff
+                        .added_tiles
+                        .push((category.name.clone(), other_tile.name.clone())),
+                    Some(tile) => {
+                        let fields = tile.changed_fields(other_tile);
+                        if !fields.is_empty() {
+                            diff.modified_tiles.push(TileChange {
+                                category: category.name.clone(),
+                                name: tile.name.clone(),
+                                fields,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        diff
+    }
+    /// Looks a category up by name. Names should be unique, if they aren't the first one in order wins.
+    /// A plain scan, cheap enough per frame for packs of a few thousand tiles.
+    pub fn find_category(&self, name: &str) -> Option<&TileCategory> {
+        self.categories.iter().find(|category| category.name == name)
+    }
+    pub fn find_category_mut(&mut self, name: &str) -> Option<&mut TileCategory> {
+        self.categories.iter_mut().find(|category| category.name == name)
+    }
+    /// Looks a tile up by name across all categories, the first match in [`iter_tiles`](Self::iter_tiles) order wins
+    pub fn find_tile(&self, name: &str) -> Option<(&TileCategory, &TileInfo)> {
+        self.iter_tiles().find(|(_, tile)| tile.name == name)
+    }
+    /// Mutable [`find_tile`](Self::find_tile). The tile borrows from its category,
+    /// so the category comes back as its position in `categories` like in [`iter_tiles_mut`](Self::iter_tiles_mut)
+    pub fn find_tile_mut(&mut self, name: &str) -> Option<(usize, &mut TileInfo)> {
+        self.iter_tiles_mut().find(|(_, tile)| tile.name == name)
+    }
+    /// Reorders categories alphabetically, ignoring case. Indices stay as they are, so sorting by [`SortKey::Index`] brings the old order back.
+    pub fn sort_categories_by_name(&mut self) {
+        self.categories
+            .sort_by(|cat1, cat2| compare_names(&cat1.name, &cat2.name));
+    }
+    /// Reorders the tiles of every category alphabetically, ignoring case
+    pub fn sort_tiles_by_name(&mut self) {
+        for category in self.categories.iter_mut() {
+            category.tiles.sort_by(|tile1, tile2| compare_names(&tile1.name, &tile2.name));
+        }
+    }
+    /// Reorders categories without touching their indices. Both sorts are stable, ties keep their current order.
+    pub fn sort_categories(&mut self, key: SortKey) {
+        match key {
+            SortKey::Index => self.categories.sort_by_key(|category| category.index),
+            SortKey::Name => self.sort_categories_by_name(),
+        }
+    }
+    /// Indices claimed by more than one category, with the names of those categories, lowest index first
+    pub fn check_index_collisions(&self) -> Vec<(usize, Vec<String>)> {
+        let mut claims: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for category in self.categories.iter() {
+            claims.entry(category.index).or_default().push(category.name.clone());
+        }
+        claims.into_iter().filter(|(_, names)| names.len() > 1).collect()
+    }
+    /// Orders categories by index and renumbers them from 0.
+    /// Categories sharing an index keep their current relative order, so the result doesn't depend on anything else.
     pub fn sort_and_normalize_categories(&mut self) {
+        self.sort_and_normalize_categories_from(0)
+    }
+    /// Normalizes the way loading does: numbering from [`category_start_index`](lingo_de::ParseOptions::category_start_index),
+    /// after [pruning](Self::prune_empty_categories) if [`prune_empty_categories`](lingo_de::ParseOptions::prune_empty_categories) is set
+    pub fn sort_and_normalize_categories_with(&mut self, options: &lingo_de::ParseOptions) {
+        if options.prune_empty_categories {
+            let pruned = self.prune_empty_categories();
+            if pruned > 0 {
+                log::info!("dropped {pruned} categories without tiles");
+            }
+        }
+        self.sort_and_normalize_categories_from(options.category_start_index)
+    }
+    /// [`sort_and_normalize_categories`](Self::sort_and_normalize_categories) with numbering starting at `start_index`,
+    /// for packs where an index 0 category would be mishandled
+    pub fn sort_and_normalize_categories_from(&mut self, start_index: usize) {
+        for (index, names) in self.check_index_collisions() {
+            log::warn!("categories {names:?} all claim index {index}, keeping them in their current order");
+        }
+        //sort_by is stable, that's what breaks ties
         self.categories
             .sort_by(|cat1, cat2| cat1.index.cmp(&cat2.index));
         for index in indices(&self.categories) {
-            self.categories[index].index = index;
+            self.categories[index].index = start_index + index;
+        }
+    }
+}
+
+/// Lists png files in `root` and in the category subfolders that no tile refers to.
+/// Names are compared case-insensitively, same as the editor does on windows.
+pub fn orphan_images(init: &TileInit, root: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut folders = vec![root.to_path_buf()];
+    let mut referenced = std::collections::HashSet::new();
+    for category in init.categories.iter() {
+        let category_folder = category.subfolder.as_ref().map(|sub| {
+            root.join(sub.strip_prefix(&init.root).unwrap_or(sub.as_path()))
+        });
+        if let Some(folder) = &category_folder {
+            if !folders.contains(folder) {
+                folders.push(folder.clone());
+            }
+        }
+        for tile in category.tiles.iter() {
+            referenced.insert(tile.image_path(root).to_string_lossy().to_lowercase());
+            if let Some(folder) = &category_folder {
+                referenced.insert(tile.image_path(folder).to_string_lossy().to_lowercase());
+            }
         }
     }
+    let mut res: Vec<std::path::PathBuf> = folders
+        .iter()
+        .filter_map(|folder| std::fs::read_dir(folder).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .map(|ext| ext.eq_ignore_ascii_case("png"))
+                    .unwrap_or(false)
+        })
+        .filter(|path| !referenced.contains(&path.to_string_lossy().to_lowercase()))
+        .collect();
+    res.sort();
+    res
 }
 
+#[cfg(feature = "gui")]
 const CELL_TEXTURE_DIM: usize = 5;
 pub static ASSETS_DIR: Dir<'_> = include_dir!("./assets");
 
@@ -287,6 +1706,7 @@ thread_local! {
         (TileCell::Glass,               [000, 000, 255]),
     ].into_iter().collect();
 
+    #[cfg(feature = "gui")]
     static TILE_CELL_TEXTURES: HashMap<TileCell, multiarray::Array2D<PrimitiveColor>> = vec![
         TileCell::Any,
         TileCell::Air,
@@ -305,6 +1725,7 @@ thread_local! {
         (TileType::VoxelStructRockType, "voxelStructRockType"),
         (TileType::VoxelStructDisplaceV, "voxelStructRandomDisplaceVertical"),
         (TileType::VoxelStructDisplaceH, "voxelStructRandomDisplaceHorizontal"),
+        (TileType::VoxelStructSandType, "voxelStructSandType"),
         (TileType::Box, "box")
     ].into_iter().collect();
 }
@@ -1,29 +1,85 @@
 use lazy_static::lazy_static;
 
-use crate::{app::AppError, utl::indices, *};
-use std::collections::HashMap;
+use crate::{utl::{decode_init_bytes, indices}, *};
+use std::collections::{BTreeMap, HashMap};
 
-//todo: make sure support for negative numbers is not needed
-
-const REGEXSTR_PROPS: &str = r#"\#(\w+):("[\\\w\d\s+_-]*?"|point\([\s\d,-]*?\)|\[\s*((\s*?,?\s*?(-?\d+|"[\w\d\s]*?"))*?)\s*\]|\d+)"#; // selects all flat properties from a tile serialization string. capture group 1 is property name and capture group 2 is property value (then fed to one of the lower regexes)
-const REGEXSTR_CATEGORY: &str = r#""(.+?)"\s*?,\s*?color\((.+?)\)"#;
-const REGEXSTR_NUMBER: &str = r#"(-?\d+?)"#; //matches unsigned numbers. look at capture group 1 for contents
-const REGEXSTR_STRING: &str = r#""([\w\d\s]*?)""#; //matches "-delimited strings. look at capture group 1 for contents
-const REGEXSTR_ARRAY: &str = r#"\[(.*?)\]"#; //matches stuff in square brackets. look at capture group 1 for contents
-const REGEXSTR_POINT: &str = r#"point\(([\d,]*?)\)"#; //matches lingo points. look at capture group 1  for contents
+const REGEXSTR_PROPS: &str = r#"\#(\w+):("(?:[^"\\]|\\.)*"|point\([\s\d,-]*?\)|color\([\s\d.,-]*?\)|\[\s*((\s*?,?\s*?(-?\d+(?:\.\d+)?|"(?:[^"\\]|\\.)*"|[A-Za-z_]\w*))*?)\s*\]|-?\d+(?:\.\d+|(?:,\d{3})*)|(?i:void))"#; // selects all flat properties from a tile serialization string. capture group 1 is property name and capture group 2 is property value (then fed to one of the lower regexes)
+const REGEXSTR_CATEGORY: &str = r#""(.+?)"\s*?,\s*?(color\(.+?\))"#;
 const REGEXSTR_SPLITCOMMAS: &str = r#"\s*,\s*"#; //splits items by commas with spaces on either side
-const REGEXSTR_CATEGORY_INDEX: &str = r#"--CATEGORY_INDEX:(\d+)$"#;
+const REGEXSTR_CATEGORY_INDEX: &str = r#"--CATEGORY_INDEX:(\d+)\s*$"#; //\s so a stray \r or space doesn't hide it
+const REGEXSTR_THOUSANDS: &str = r#"^-?\d{1,3}(,\d{3})+$"#; //matches hand-written numbers like 1,000
+
+// the only place regexes get compiled, each once on first use
+lazy_static! {
+    static ref REGEX_PROPERTIES: regex::Regex = regex::Regex::new(REGEXSTR_PROPS).unwrap();
+    static ref REGEX_CATEGORY: regex::Regex = regex::Regex::new(REGEXSTR_CATEGORY).unwrap();
+    static ref REGEX_SPLITCOMMAS: regex::Regex = regex::Regex::new(REGEXSTR_SPLITCOMMAS).unwrap();
+    static ref REGEX_CATEGORY_INDEX: regex::Regex =
+        regex::Regex::new(REGEXSTR_CATEGORY_INDEX).unwrap();
+    static ref REGEX_THOUSANDS: regex::Regex = regex::Regex::new(REGEXSTR_THOUSANDS).unwrap();
+}
+const BOM: char = '\u{feff}'; //byte order mark some windows editors start files with
+
+/// Knobs for how forgiving the parser is. The editor loads leniently, `strict` is meant for validating packs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// a missing `nm`, `sz`, `tp` or `specs` fails with [`DeserError::MissingValue`] and sloppy numbers aren't recovered
+    pub strict: bool,
+    /// lines starting with any of these are comments, kept with the category or tile after them. vanilla only uses `--`
+    pub comment_prefixes: Vec<String>,
+    /// number very old inits put between rows of specs. stripped when the cell count only adds up without it
+    pub legacy_row_sentinel: Option<i32>,
+    /// run [`TileInfo::validate`] on every tile, so broken ones land in `errored_lines` instead
+    pub validate: bool,
+    /// how many folders deep to look for category subfolders. `None` searches the whole tree
+    pub max_subfolder_depth: Option<usize>,
+    /// index the first category gets after sorting, see [`TileInit::sort_and_normalize_categories_from`]
+    pub category_start_index: usize,
+    /// keep the source text of every property in [`TileInfo::raw_props`]
+    pub keep_raw_props: bool,
+    /// drop categories without tiles after loading, subfolder ones stay. see [`TileInit::prune_empty_categories`]
+    pub prune_empty_categories: bool,
+}
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            comment_prefixes: vec![String::from("--")],
+            legacy_row_sentinel: None,
+            validate: false,
+            max_subfolder_depth: None,
+            category_start_index: 0,
+            keep_raw_props: false,
+            prune_empty_categories: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn is_comment(&self, line: &str) -> bool {
+        self.comment_prefixes
+            .iter()
+            .any(|prefix| line.starts_with(prefix.as_str()))
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LingoData {
     Number(i32),
+    Float(f64),
     String(String),
     Array(Vec<Box<LingoData>>),
     Point(Vec<i32>),
+    Color(PrimitiveColor),
+    Bool(bool),
+    Void,
     InvalidOrNull(String),
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DeserError {
     RegexMatchFailed(String),
     ContentsNotParsed(String),
@@ -37,32 +93,110 @@ pub enum DeserError {
     NoCategory(TileInfo),
     IOError,
     MissingFile,
-    MissingValue,
+    /// a required property isn't there at all, holds its key. only reported in strict mode
+    MissingValue(String),
     Todo,
 }
 
+/// A line that failed to parse and why. `source` is the file it was read from, when known.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeserErrorReport {
+    pub text: String,
+    pub error: DeserError,
+    pub source: Option<std::path::PathBuf>,
+    pub line_number: Option<usize>, //1-based
+    pub byte_offset: Option<usize>, //where the line starts
+}
+
+impl DeserErrorReport {
+    pub fn new(text: &str, error: DeserError) -> Self {
+        Self {
+            text: text.to_string(),
+            error,
+            source: None,
+            line_number: None,
+            byte_offset: None,
+        }
+    }
+    pub fn at(mut self, line_number: usize, byte_offset: usize) -> Self {
+        self.line_number = Some(line_number);
+        self.byte_offset = Some(byte_offset);
+        self
+    }
+}
+
+impl std::fmt::Display for DeserErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(source) = &self.source {
+            write!(f, "{} ", source.display())?;
+        }
+        match self.line_number {
+            Some(line_number) => write!(f, "line {line_number}: {:?}", self.error),
+            None => write!(f, "{:?}", self.error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEndingReport {
+    /// single line, nothing to report
+    NoLineBreaks,
+    Lf,
+    Crlf,
+    Mixed { lf: usize, crlf: usize },
+}
+
+/// Which line endings `text` uses. tileman reads any mix, but other tools may not like them mixed
+pub fn detect_line_ending_consistency(text: &str) -> LineEndingReport {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (lf, crlf) {
+        (0, 0) => LineEndingReport::NoLineBreaks,
+        (_, 0) => LineEndingReport::Lf,
+        (0, _) => LineEndingReport::Crlf,
+        (lf, crlf) => LineEndingReport::Mixed { lf, crlf },
+    }
+}
+
+/// `text.lines()`, along with each line's 1-based number and the byte offset it starts at.
+/// A byte order mark windows tools put in front of the first line is dropped, offsets still count it.
+fn numbered_lines(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    let mut offset = 0;
+    text.split_inclusive('\n')
+        .enumerate()
+        .map(move |(index, raw)| {
+            let start = offset;
+            offset += raw.len();
+            let line = raw.strip_suffix('\n').unwrap_or(raw);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let line = match index {
+                0 => line.strip_prefix(BOM).unwrap_or(line),
+                _ => line,
+            };
+            (index + 1, start, line)
+        })
+}
+
 impl LingoData {
     pub fn parse<'a>(text: &str) -> Result<Self, DeserError> {
-        // if text == "void" {
-        //     return Ok(LingoData::Null);
-        // }
-        lazy_static! {
-            static ref REGEX_NUMBER: regex::Regex = regex::Regex::new(REGEXSTR_NUMBER).unwrap();
-            static ref REGEX_STRING: regex::Regex = regex::Regex::new(REGEXSTR_STRING).unwrap();
-            static ref REGEX_ARRAY: regex::Regex = regex::Regex::new(REGEXSTR_ARRAY).unwrap();
-            static ref REGEX_POINT: regex::Regex = regex::Regex::new(REGEXSTR_POINT).unwrap();
-            static ref REGEX_SPLITCOMMAS: regex::Regex =
-                regex::Regex::new(REGEXSTR_SPLITCOMMAS).unwrap();
-        }
         let text = text.trim(); //damn you random whitespaces
         let mut res = Ok(LingoData::InvalidOrNull(text.to_string()));
-        if text.starts_with("[") && text.ends_with("]") {
-            let spl = REGEX_SPLITCOMMAS.split(&text[1..text.len() - 1]);
+        let array_end = match text.starts_with('[') {
+            true => matching_bracket(text),
+            false => None,
+        };
+        if let Some(array_end) = array_end {
+            //anything after the matching bracket is hand-editing junk and gets dropped
+            //elements that don't parse stay as InvalidOrNull so indices keep lining up
+            let spl = split_top_level(&text[1..array_end]);
             res = Ok(Self::Array(
                 spl.into_iter()
-                    .filter_map(|sub| match LingoData::parse(sub) {
-                        Ok(ld) => Some(Box::new(ld)),
-                        Err(_) => None,
+                    .map(|sub| {
+                        Box::new(
+                            LingoData::parse(sub)
+                                .unwrap_or_else(|_| LingoData::InvalidOrNull(sub.trim().to_string())),
+                        )
                     })
                     .collect(),
             ))
@@ -76,16 +210,70 @@ impl LingoData {
                     })
                     .collect(),
             ))
+        } else if text.starts_with("color(") && text.ends_with(")") {
+            if let Ok(color) = parse_color_channels(&text[6..text.len() - 1]) {
+                res = Ok(Self::Color(color))
+            }
+        } else if text.eq_ignore_ascii_case("void") {
+            res = Ok(Self::Void)
+        } else if text.eq_ignore_ascii_case("true") {
+            res = Ok(Self::Bool(true))
+        } else if text.eq_ignore_ascii_case("false") {
+            res = Ok(Self::Bool(false))
         } else if text.starts_with("\"") && text.ends_with("\"") {
-            res = Ok(LingoData::String(String::from(&text[1..text.len() - 1])))
+            res = Ok(LingoData::String(unescape_string(&text[1..text.len() - 1])))
         } else if let Ok(val) = text.parse::<i32>() {
             res = Ok(LingoData::Number(val))
+        } else if is_float_literal(text) {
+            if let Ok(val) = text.parse::<f64>() {
+                res = Ok(LingoData::Float(val))
+            }
         }
         res
     }
+    /// Lingo text for this value, `parse` turns it back into an equal `LingoData`
+    pub fn to_lingo_string(&self) -> String {
+        match self {
+            LingoData::Number(num) => num.to_string(),
+            LingoData::Float(num) => format!("{num:?}"), //debug formatting keeps the decimal point on whole floats
+            //backslashes first, or the ones escaping quotes would get doubled too
+            LingoData::String(string) => {
+                format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            LingoData::Array(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(|item| item.to_lingo_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            LingoData::Point(coords) => format!(
+                "point({})",
+                coords
+                    .iter()
+                    .map(|coord| coord.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            LingoData::Color([r, g, b]) => format!("color({r},{g},{b})"),
+            LingoData::Bool(true) => String::from("TRUE"),
+            LingoData::Bool(false) => String::from("FALSE"),
+            LingoData::Void => String::from("void"),
+            LingoData::InvalidOrNull(text) => text.clone(),
+        }
+    }
     pub fn as_number(&self) -> Result<i32, DeserError> {
         if let LingoData::Number(num) = self {
             Ok(*num)
+        } else if let LingoData::Float(num) = self {
+            let truncated = *num as i32;
+            match truncated as f64 == *num {
+                true => Ok(truncated),
+                false => Err(DeserError::DataConvertFailed(format!(
+                    "{num} can not be a whole number without losing precision"
+                ))),
+            }
         } else {
             Err(DeserError::DataConvertFailed(format!(
                 "{:?} not a number",
@@ -93,6 +281,16 @@ impl LingoData {
             )))
         }
     }
+    pub fn as_float(&self) -> Result<f64, DeserError> {
+        match self {
+            LingoData::Float(num) => Ok(*num),
+            LingoData::Number(num) => Ok(*num as f64),
+            _ => Err(DeserError::DataConvertFailed(format!(
+                "{:?} not a float",
+                self
+            ))),
+        }
+    }
     pub fn as_string(&self) -> Result<String, DeserError> {
         if let LingoData::String(string) = self {
             Ok(string.clone())
@@ -122,6 +320,32 @@ impl LingoData {
             )))
         }
     }
+    pub fn as_color(&self) -> Result<PrimitiveColor, DeserError> {
+        if let LingoData::Color(color) = self {
+            Ok(*color)
+        } else {
+            Err(DeserError::DataConvertFailed(format!(
+                "could not build color from {:?}",
+                self
+            )))
+        }
+    }
+    /// `(x, y)` of a point with exactly two components
+    pub fn as_point2(&self) -> Result<(i32, i32), DeserError> {
+        match self {
+            LingoData::Point(axes) => match axes[..] {
+                [x, y] => Ok((x, y)),
+                _ => Err(DeserError::InvalidValue(format!(
+                    "point needs 2 components, got {}",
+                    axes.len()
+                ))),
+            },
+            _ => Err(DeserError::DataConvertFailed(format!(
+                "could not build point from {:?}",
+                self
+            ))),
+        }
+    }
     pub fn as_number_array(&self) -> Result<Vec<i32>, DeserError> {
         if let LingoData::Array(numbers) = self {
             Ok(numbers
@@ -141,40 +365,241 @@ impl LingoData {
             )))
         }
     }
+    /// Whole floats like `1.0` count as cell codes since corrupt exports write them,
+    /// fractional ones fail instead of being dropped and shortening the grid.
+    /// Other cells that aren't a valid code become [`TileCell::Any`] so the grid keeps its shape.
     pub fn as_tilecell_array(&self) -> Result<Vec<TileCell>, DeserError> {
-        let number_array = self.as_number_array();
-        if let Ok(arr) = number_array {
-            return Ok(arr
-                .into_iter()
-                .map(|item| TileCell::from_number(item))
-                .filter_map(|x| x.ok())
-                .collect());
+        self.as_tilecell_array_with(false)
+    }
+    /// `strict` turns cells that aren't a valid code into an [`DeserError::InvalidValue`] instead of [`TileCell::Any`]
+    pub fn as_tilecell_array_with(&self, strict: bool) -> Result<Vec<TileCell>, DeserError> {
+        if let LingoData::Array(items) = self {
+            let mut cells = Vec::with_capacity(items.len());
+            for (index, item) in items.iter().enumerate() {
+                let cell = match **item {
+                    LingoData::Number(num) => TileCell::from_number(num),
+                    LingoData::Float(num) if num.fract() == 0.0 => TileCell::from_number(num as i32),
+                    LingoData::Float(num) => {
+                        return Err(DeserError::InvalidValue(format!(
+                            "fractional cell code {num}"
+                        )))
+                    }
+                    ref other => Err(DeserError::InvalidValue(format!(
+                        "cell {index} is {:?}, not a cell code",
+                        other.to_lingo_string()
+                    ))),
+                };
+                match (cell, strict) {
+                    (Ok(cell), _) => cells.push(cell),
+                    (Err(err), true) => return Err(err),
+                    (Err(err), false) => {
+                        log::warn!("{err:?}, using any");
+                        cells.push(TileCell::Any)
+                    }
+                }
+            }
+            return Ok(cells);
         };
         Err(DeserError::DataConvertFailed(format!(
             "could not build tilecellArray from {:?}",
             self
         )))
     }
+    /// lingo writes "no value" as either `0` or `void` depending on who made the file
     pub fn as_null_if_zero(self) -> Self {
         if let LingoData::Number(num_item) = self {
             if num_item == 0 {
-                return Self::InvalidOrNull("NULL".to_string());
+                return Self::Void;
             }
         }
         self
     }
 }
 
-pub fn parse_tile_info<'a>(text: &'a str, from_vanilla: bool) -> Result<TileInfo, DeserError> {
-    lazy_static::lazy_static! {
-        static ref REGEX_PROPERTIES: regex::Regex = regex::Regex::new(REGEXSTR_PROPS).unwrap();
+fn as_repeat_layers(data: LingoData) -> Result<RepeatLayers, DeserError> {
+    let LingoData::Array(items) = &data else {
+        return Err(DeserError::InvalidValue(format!("repeatL {data:?} isn't a list")));
+    };
+    items
+        .iter()
+        .map(|item| match **item {
+            LingoData::Number(layers) => Ok(layers),
+            _ => Err(DeserError::InvalidValue(format!("repeatL entry {item:?} isn't a whole number"))),
+        })
+        .collect::<Result<Vec<i32>, DeserError>>()
+        .map(RepeatLayers)
+}
+
+fn strip_row_sentinels(data: LingoData, sentinel: i32, expected_cells: usize) -> LingoData {
+    match data {
+        LingoData::Array(items) if items.len() != expected_cells => {
+            let stripped: Vec<Box<LingoData>> = items
+                .iter()
+                .filter(|item| ***item != LingoData::Number(sentinel))
+                .cloned()
+                .collect();
+            match stripped.len() == expected_cells {
+                true => LingoData::Array(stripped),
+                false => LingoData::Array(items),
+            }
+        }
+        other => other,
+    }
+}
+
+/// Digits with a sign, decimal point or exponent. Keeps words rust would parse as floats, like `inf`, out.
+fn is_float_literal(text: &str) -> bool {
+    text.chars().any(|char| char.is_ascii_digit())
+        && text
+            .chars()
+            .all(|char| char.is_ascii_digit() || matches!(char, '.' | '-' | '+' | 'e' | 'E'))
+}
+
+/// One color channel, out of range numbers are clamped to 0-255 instead of dropped so the other channels keep their place
+fn color_component(text: &str) -> Option<u8> {
+    text.trim()
+        .parse::<i64>()
+        .ok()
+        .map(|num| num.clamp(0, 255) as u8)
+}
+
+/// Reads the inside of a `color(...)`. Channels are 0-255, or 0-1 when written
+/// with a decimal point like `color(0.5, 0.2, 0.1)`. A fourth (alpha) channel is ignored.
+fn parse_color_channels(text: &str) -> Result<[u8; 3], DeserError> {
+    let invalid = || DeserError::InvalidValue(format!("invalid color {text:?}"));
+    let channels: Vec<&str> = text.split(',').map(str::trim).collect();
+    if !(3..=4).contains(&channels.len()) {
+        return Err(invalid());
+    }
+    let values = channels[..3]
+        .iter()
+        .map(|channel| channel.parse::<f64>().ok().filter(|value| value.is_finite()))
+        .collect::<Option<Vec<f64>>>()
+        .ok_or_else(invalid)?;
+    let unit_range = channels[..3].iter().any(|channel| channel.contains('.'))
+        && values.iter().all(|value| *value <= 1.0);
+    let scale = match unit_range {
+        true => 255.0,
+        false => 1.0,
+    };
+    Ok([0, 1, 2].map(|index| (values[index] * scale).round().clamp(0.0, 255.0) as u8))
+}
+
+/// Byte index of the bracket closing the one `text` starts with, brackets inside strings don't count
+fn matching_bracket(text: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, char) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match char {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' | '(' if !in_string => depth += 1,
+            ']' | ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Undoes the escaping `to_lingo_string` does: `\"` is a quote and `\\` a backslash.
+/// Any other backslash is kept as it is, hand-written inits use them in paths.
+fn unescape_string(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        match (char, chars.peek()) {
+            ('\\', Some(&next @ ('"' | '\\'))) => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(char),
+        }
+    }
+    unescaped
+}
+
+/// Splits array contents on commas that aren't inside nested brackets, parens or strings
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (index, char) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match char {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' | '(' if !in_string => depth += 1,
+            ']' | ')' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                items.push(text[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = text[start..].trim();
+    if !(items.is_empty() && last.is_empty()) {
+        items.push(last);
+    }
+    items
+}
+
+/// Lenient recovery for hand-edited scalars like `1,000`, which genuine lingo never writes.
+fn recover_thousands_separators(data: LingoData) -> LingoData {
+    match data {
+        LingoData::InvalidOrNull(text) if REGEX_THOUSANDS.is_match(&text) => {
+            match text.replace(',', "").parse::<i32>() {
+                Ok(num) => LingoData::Number(num),
+                Err(_) => LingoData::InvalidOrNull(text),
+            }
+        }
+        other => other,
     }
+}
+
+pub fn parse_tile_info<'a>(text: &'a str, from_vanilla: bool) -> Result<TileInfo, DeserError> {
+    parse_tile_info_with(text, from_vanilla, &ParseOptions::default())
+}
+
+pub fn parse_tile_info_with(
+    text: &str,
+    from_vanilla: bool,
+    options: &ParseOptions,
+) -> Result<TileInfo, DeserError> {
+    const REQUIRED_PROPS: [&str; 4] = ["nm", "sz", "tp", "specs"];
+    const KNOWN_PROPS: [&str; 10] = [
+        "nm", "sz", "specs", "specs2", "tp", "repeatL", "bfTiles", "rnd", "ptPos", "tags",
+    ];
     let mut map: HashMap<String, String> = HashMap::new();
+    let mut extra_props = Vec::new();
     for cap in REGEX_PROPERTIES.captures_iter(text) {
         let name = &cap[1];
         let val = &cap[2];
+        if !KNOWN_PROPS.contains(&name) {
+            extra_props.push((String::from(name), String::from(val)));
+        }
         map.insert(String::from(name), String::from(val));
     }
+    if options.strict {
+        if let Some(key) = REQUIRED_PROPS.iter().find(|key| !map.contains_key(**key)) {
+            return Err(DeserError::MissingValue(key.to_string()));
+        }
+    }
 
     macro_rules! get_prop {
         ($name:ident, $key:literal) => {
@@ -185,6 +610,15 @@ pub fn parse_tile_info<'a>(text: &'a str, from_vanilla: bool) -> Result<TileInfo
             let $name = LingoData::parse($name);
         };
     }
+    macro_rules! get_scalar_prop {
+        ($name:ident, $key:literal) => {
+            get_prop!($name, $key);
+            let $name = match options.strict {
+                true => $name,
+                false => $name.map(recover_thousands_separators),
+            };
+        };
+    }
     macro_rules! cast_enum {
         ($origname:ident, $newname:ident, $key:literal, $entry:ident) => {
             let $newname = match $origname {
@@ -202,71 +636,156 @@ pub fn parse_tile_info<'a>(text: &'a str, from_vanilla: bool) -> Result<TileInfo
     cast_enum!(name, name, "nm", String);
     get_prop!(size, "sz");
     cast_enum!(size, size, "sz", Point);
+    let size = size.and_then(|axes| match axes.len() {
+        // 3d voxel tiles add a depth, validate checks that against the type
+        3 => Ok(axes),
+        _ => LingoData::Point(axes)
+            .as_point2()
+            .map(|(width, height)| vec![width, height]),
+    });
     get_prop!(specs, "specs");
     get_prop!(specs2, "specs2");
+    let (specs, specs2) = match (options.legacy_row_sentinel, &size) {
+        (Some(sentinel), Ok(size)) if size.len() >= 2 => {
+            let expected_cells = (size[0] * size[1]).max(0) as usize;
+            (
+                specs.map(|data| strip_row_sentinels(data, sentinel, expected_cells)),
+                specs2.map(|data| strip_row_sentinels(data, sentinel, expected_cells)),
+            )
+        }
+        _ => (specs, specs2),
+    };
     get_prop!(tile_type, "tp");
     cast_enum!(tile_type, tile_type, "tp", String);
     get_prop!(repeat_layers, "repeatL");
-    get_prop!(buffer_tiles, "bfTiles");
+    get_scalar_prop!(buffer_tiles, "bfTiles");
     cast_enum!(buffer_tiles, buffer_tiles, "bfTiles", Number);
-    get_prop!(random_vars, "rnd");
+    get_scalar_prop!(random_vars, "rnd");
     cast_enum!(random_vars, random_vars, "rnd", Number);
-    get_prop!(preview_pos, "ptPos");
+    get_scalar_prop!(preview_pos, "ptPos");
     cast_enum!(preview_pos, preview_pos, "ptPos", Number);
     get_prop!(tags, "tags");
     //cast_enum!(tags, "tags");
-    let res = TileInfo {
+    let mut res = TileInfo {
         name: name?,
         size: size?,
-        specs: specs?.as_tilecell_array()?,
-        specs2: specs2?.as_null_if_zero().as_tilecell_array().ok(),
+        // inert tiles write `#specs:0` for no collision
+        specs: match specs?.as_null_if_zero() {
+            LingoData::Void => SpecData::from(Vec::new()),
+            specs => specs.as_tilecell_array_with(options.strict)?.into(),
+        },
+        specs2: match map.contains_key("specs2") {
+            false => Specs2::Missing,
+            true => match specs2?.as_null_if_zero().as_tilecell_array_with(options.strict) {
+                Ok(cells) => Specs2::Layer(cells.into()),
+                Err(err @ DeserError::InvalidValue(_)) if options.strict => return Err(err),
+                Err(_) => Specs2::Zero,
+            },
+        },
         tile_type: TileType::from_string(tile_type?.as_str())?,
-        repeat_layers: repeat_layers.and_then(|x| x.as_number_array()).ok(),
+        // a repeatL that's there but isn't a list of whole numbers is an error, not a missing one
+        repeat_layers: match map.contains_key("repeatL") {
+            false => None,
+            true => Some(as_repeat_layers(repeat_layers?)?),
+        },
         buffer_tiles: buffer_tiles?,
-        random_vars: random_vars.ok(),
+        random_vars: match options.strict && map.contains_key("rnd") {
+            true => Some(random_vars?),
+            false => random_vars.ok(),
+        },
         preview_pos: preview_pos?,
-        tags: tags?.as_string_array().unwrap_or(Vec::new()),
+        // filled in below like add_tag does: trimmed, no empty ones and no duplicates in any casing
+        tags: Vec::new(),
+        extra_props,
+        raw_props: match options.keep_raw_props {
+            true => map.into_iter().collect(),
+            false => BTreeMap::new(),
+        },
         active: from_vanilla,
+        comments: Vec::new(),
     };
+    for tag in tags?.as_string_array().unwrap_or(Vec::new()) {
+        res.add_tag(&tag);
+    }
+    if options.validate {
+        res.validate()?;
+    }
     Ok(res)
     //Err(DeserError::Todo)
 }
 
+/// Tiles only: every tile line in `text` in order, whatever category it was under.
+/// Category headers and comments are skipped, use [`parse_tile_info_by_category`] to keep which tile was where.
 pub fn parse_tile_info_multiple<'a>(
     text: &'a str,
 ) -> Result<(Vec<TileInfo>, DeserErrorReports), DeserError> {
+    let (groups, errors) = parse_tile_info_by_category(text, &ParseOptions::default())?;
+    let tiles = groups.into_iter().flat_map(|(_, tiles)| tiles).collect();
+    Ok((tiles, errors))
+}
+
+/// A category header, if there was one, and the tiles listed under it
+pub type CategoryTiles = (Option<TileCategory>, Vec<TileInfo>);
+
+/// Tile lines grouped under the category header above them, in file order. Tiles before the first header come
+/// first with no category. A `--CATEGORY_INDEX:n` line of its own sets the index of the header before it.
+/// Unlike [`parse_tile_init`] nothing is merged, sorted or renumbered. `options` apply as in [`parse_tile_init_with`].
+pub fn parse_tile_info_by_category(
+    text: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<CategoryTiles>, DeserErrorReports), DeserError> {
     let mut errors = Vec::new();
-    let mut tiles = Vec::new();
-    for line in text.lines().filter(|line| !line.starts_with('-') && !line.trim().is_empty()) {
-        match parse_tile_info(line, false) {
-            Ok(tile) => tiles.push(tile),
-            Err(err) => errors.push((line.to_string(), err)),
+    let mut groups: Vec<CategoryTiles> = Vec::new();
+    for (line_number, offset, line) in numbered_lines(text).filter(|(_, _, line)| !line.trim().is_empty()) {
+        if line.starts_with("-[") {
+            match parse_category_header(line) {
+                Ok(category) => groups.push((Some(category), Vec::new())),
+                Err(err) => errors.push(DeserErrorReport::new(line, err).at(line_number, offset)),
+            }
+        } else if let Some(caps) = REGEX_CATEGORY_INDEX.captures(line).filter(|_| options.is_comment(line)) {
+            match groups.last_mut() {
+                Some((Some(category), _)) => category.index = caps[1].parse().unwrap_or(category.index),
+                _ => log::warn!("line {line_number}: {line:?} comes before any category"),
+            }
+        } else if options.is_comment(line) {
+            continue;
+        } else {
+            // anything else is meant to be a tile, even if it starts with `-`, so mistakes get reported
+            match parse_tile_info_with(line, false, options) {
+                Ok(tile) => match groups.last_mut() {
+                    Some((_, tiles)) => tiles.push(tile),
+                    None => groups.push((None, vec![tile])),
+                },
+                Err(err) => errors.push(DeserErrorReport::new(line, err).at(line_number, offset)),
+            }
         }
     }
-    return Ok((tiles, errors));
+    Ok((groups, errors))
+}
+
+/// Wraps a single tile line in the smallest init that still loads, for attaching to bug reports.
+/// Parsing the result with `parse_tile_init` reports the same error the line had originally.
+pub fn minimal_repro(line: &str) -> String {
+    let category = TileCategory::new_main(String::from("Repro"), [255, 0, 0], 0);
+    format!(
+        "{}\n{}\n",
+        lingo_ser::serialize_category_header(&category),
+        line.trim()
+    )
 }
 
 pub fn parse_category_header<'a>(text: &'a str) -> Result<TileCategory, DeserError> {
-    lazy_static! {
-        static ref REGEX_CATEGORY: regex::Regex = regex::Regex::new(REGEXSTR_CATEGORY).unwrap();
-        static ref REGEX_SPLITCOMMAS: regex::Regex =
-            regex::Regex::new(REGEXSTR_SPLITCOMMAS).unwrap();
-        static ref REGEX_CATEGORY_INDEX: regex::Regex =
-            regex::Regex::new(REGEXSTR_CATEGORY_INDEX).unwrap();
-    }
     if let Some(caps) = REGEX_CATEGORY.captures(text) {
         let nm = &caps[1];
-        let colstr = &caps[2];
-        let split = REGEX_SPLITCOMMAS.split(colstr);
-        let col: Vec<u8> = split
-            .into_iter()
-            .filter_map(|sub| sub.parse::<u8>().ok())
-            .collect();
-        let color = [
-            *col.get(0).unwrap_or(&0u8),
-            *col.get(1).unwrap_or(&0u8),
-            *col.get(2).unwrap_or(&0u8),
-        ];
+        let color = match LingoData::parse(&caps[2])? {
+            LingoData::Color(color) => color,
+            other => {
+                return Err(DeserError::InvalidValue(format!(
+                    "invalid color {:?}",
+                    other.to_lingo_string()
+                )))
+            }
+        };
         let index = match REGEX_CATEGORY_INDEX.captures(text) {
             Some(caps) => caps[1].parse().unwrap_or(0),
             None => 0,
@@ -282,23 +801,160 @@ pub fn parse_tile_init<'a>(
     text: String,
     additional_categories: Vec<TileCategory>,
     root: std::path::PathBuf,
+) -> Result<TileInit, AppError> {
+    parse_tile_init_with(text, additional_categories, root, &ParseOptions::default())
+}
+
+/// [`parse_tile_init`] for raw file contents, decoded as UTF-8 or Windows-1252, see [`utl::decode_init_bytes`]
+pub fn parse_tile_init_bytes(
+    bytes: &[u8],
+    additional_categories: Vec<TileCategory>,
+    root: std::path::PathBuf,
+) -> Result<TileInit, AppError> {
+    parse_tile_init_bytes_with(bytes, additional_categories, root, &ParseOptions::default())
+}
+
+pub fn parse_tile_init_bytes_with(
+    bytes: &[u8],
+    additional_categories: Vec<TileCategory>,
+    root: std::path::PathBuf,
+    options: &ParseOptions,
+) -> Result<TileInit, AppError> {
+    let (text, _) = decode_init_bytes(bytes);
+    parse_tile_init_with(text, additional_categories, root, options)
+}
+
+/// Reads an init or color file whatever its encoding, see [`utl::decode_init_bytes`].
+/// Files that aren't UTF-8 are logged, saving writes them back as UTF-8.
+pub fn read_init_text(path: &std::path::Path) -> std::io::Result<String> {
+    let bytes = std::fs::read(path)?;
+    let (text, encoding) = decode_init_bytes(&bytes);
+    if encoding != "UTF-8" {
+        log::info!("{path:?} isn't UTF-8, read it as {encoding}");
+    }
+    Ok(text)
+}
+
+pub fn parse_tile_init_with(
+    text: String,
+    additional_categories: Vec<TileCategory>,
+    root: std::path::PathBuf,
+    options: &ParseOptions,
+) -> Result<TileInit, AppError> {
+    let lines: Vec<(usize, usize, &str)> = init_lines(&text).collect();
+    let tiles = lines
+        .iter()
+        .map(|(_, _, line)| parse_init_line(line, options))
+        .collect();
+    assemble_tile_init(lines, tiles, additional_categories, root, options)
+}
+
+/// Same as [`parse_tile_init_with`], but tile lines are parsed on the rayon pool.
+/// The result is identical to the sequential version, including the order of tiles and errors.
+#[cfg(feature = "parallel")]
+pub fn parse_tile_init_parallel(
+    text: String,
+    additional_categories: Vec<TileCategory>,
+    root: std::path::PathBuf,
+    options: &ParseOptions,
+) -> Result<TileInit, AppError> {
+    use rayon::prelude::*;
+    let lines: Vec<(usize, usize, &str)> = init_lines(&text).collect();
+    let tiles = lines
+        .par_iter()
+        .map(|(_, _, line)| parse_init_line(line, options))
+        .collect();
+    assemble_tile_init(lines, tiles, additional_categories, root, options)
+}
+
+/// [`load_tile_init_with`] using the parallel parsers for both the main init and the subfolders
+#[cfg(feature = "parallel")]
+pub fn load_tile_init_parallel(
+    root: std::path::PathBuf,
+    options: &ParseOptions,
+) -> Result<TileInit, AppError> {
+    let mut subfolder_errors = Vec::new();
+    let additional_categories = collect_categories_from_subfolders_parallel(root.clone(), options)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(category, errors)| {
+            subfolder_errors.extend(errors);
+            category
+        })
+        .collect();
+    let main_init_path = root.join("init.txt");
+    let text = read_init_text(&main_init_path)
+        .map_err(|err| AppError::IOError(format!("{err:?}")))?;
+    let mut init = parse_tile_init_parallel(text, additional_categories, root, options)?;
+    for report in init.errored_lines.iter_mut() {
+        report.source.get_or_insert(main_init_path.clone());
+    }
+    init.errored_lines.extend(subfolder_errors);
+    Ok(init)
+}
+
+fn init_lines(text: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    numbered_lines(text).filter(|(_, _, line)| !line.trim().is_empty())
+}
+
+/// `None` for category headers and comments, those depend on what came before and are handled while assembling
+fn parse_init_line(line: &str, options: &ParseOptions) -> Option<Result<TileInfo, DeserError>> {
+    if line.starts_with("-[") || options.is_comment(line) {
+        None
+    } else {
+        Some(parse_tile_info_with(line, true, options))
+    }
+}
+
+fn assemble_tile_init(
+    lines: Vec<(usize, usize, &str)>,
+    tiles: Vec<Option<Result<TileInfo, DeserError>>>,
+    additional_categories: Vec<TileCategory>,
+    root: std::path::PathBuf,
+    options: &ParseOptions,
 ) -> Result<TileInit, AppError> {
     let mut errored_lines = Vec::new();
     let mut current_category: Option<TileCategory> = None;
     let mut categories = Vec::new();
+    // comments go with the category or tile after them
+    let mut pending_comments = Vec::new();
 
-    for line in text.lines().filter(|line| !line.starts_with("--") && !line.trim().is_empty()) {
-        if line.starts_with("-[") {
+    for ((line_number, offset, line), maybe_tile) in lines.into_iter().zip(tiles) {
+        if options.is_comment(line) {
+            pending_comments.push(line.to_string());
+        } else if let Some(maybe_new_item) = maybe_tile {
+            match maybe_new_item {
+                Ok(mut new_item) => {
+                    new_item.comments = std::mem::take(&mut pending_comments);
+                    // only add tiles if there has been a category already
+                    if let Some(category) = &mut current_category {
+                        if category.tiles.contains(&new_item) {
+                            let index = category.tiles.iter()
+                                .position(|tile| *tile == new_item)
+                                .unwrap();
+                            category.tiles[index] = new_item; 
+                        } else { 
+                            category.tiles.push(new_item); 
+                        }
+                    }
+                }
+                Err(err) => errored_lines.push(DeserErrorReport::new(line, err).at(line_number, offset)),
+            }
+        } else {
             //let maybe_new_category = Err(DeserError::MissingValue);
             let maybe_new_category = parse_category_header(line);
             match maybe_new_category {
                 Ok(mut newcat) => {
+                    newcat.comments = std::mem::take(&mut pending_comments);
                     //thalber would kill me for this, but it compiles
                     //and supposedly in rust if it compiles it's Good Code, so :leditoroverload:
                     for oldcat in additional_categories.clone() {
-                        if oldcat == newcat {
+                        if oldcat.same_identity(&newcat) {
                             newcat.subfolder = oldcat.subfolder;
                             newcat.tiles = oldcat.tiles;
+                            if newcat.comments.is_empty() {
+                                newcat.comments = oldcat.comments;
+                            }
                             break; 
                         }
                     }
@@ -308,25 +964,7 @@ pub fn parse_tile_init<'a>(
                     }
                     current_category = Some(newcat);
                 }
-                Err(err) => errored_lines.push((line.to_string(), err)),
-            }
-        } else {
-            let maybe_new_item = parse_tile_info(line, true);
-            match maybe_new_item {
-                Ok(new_item) => {
-                    // only add tiles if there has been a category already
-                    if let Some(category) = &mut current_category {
-                        if category.tiles.contains(&new_item) {
-                            let index = category.tiles.iter()
-                                .position(|tile| *tile == new_item)
-                                .unwrap();
-                            category.tiles[index] = new_item; 
-                        } else { 
-                            category.tiles.push(new_item); 
-                        }
-                    }
-                }
-                Err(err) => errored_lines.push((line.to_string(), err)),
+                Err(err) => errored_lines.push(DeserErrorReport::new(line, err).at(line_number, offset)),
             }
         }
     }
@@ -334,12 +972,14 @@ pub fn parse_tile_init<'a>(
         categories.push(category.clone());
     } else {
         // if we have "None" as the category at this point, there are none in the init file
-        return Ok(TileInit{ root, categories, errored_lines });
+        return Ok(TileInit{ root, categories, errored_lines, trailing_comments: pending_comments });
     }
     let categories_clone = categories.clone();
     categories = categories
         .into_iter()
-        .chain(additional_categories.into_iter().filter(|cat| !categories_clone.contains(cat)))
+        .chain(additional_categories.into_iter().filter(|cat| {
+            !categories_clone.iter().any(|existing| existing.same_identity(cat))
+        }))
         .collect();
     for category_index in indices(&categories) {
         let category = &mut categories[category_index];
@@ -351,82 +991,307 @@ pub fn parse_tile_init<'a>(
         root,
         categories,
         errored_lines,
+        trailing_comments: pending_comments,
     };
-    tile_init.sort_and_normalize_categories();
+    tile_init.sort_and_normalize_categories_with(options);
     Ok(tile_init)
 
     //Err(AppError::Todo)
     //Ok(res)
 }
 
+/// Parses several init.txt files and merges them in order with [`MergeStrategy::PreferOther`], so later files
+/// win when categories or tiles clash. Only the files themselves are read, not category subfolders next to them.
+/// Every errored line is tagged with its file, the root of the result is the first file's folder.
+pub fn parse_tile_inits<P: AsRef<std::path::Path>>(paths: &[P]) -> Result<TileInit, AppError> {
+    let mut merged: Option<TileInit> = None;
+    for path in paths {
+        let path = path.as_ref();
+        let text = read_init_text(path)
+            .map_err(|err| AppError::IOError(format!("{path:?}: {err:?}")))?;
+        let root = path.parent().map(|parent| parent.to_path_buf()).unwrap_or_default();
+        let mut init = parse_tile_init(text, Vec::new(), root)?;
+        for report in init.errored_lines.iter_mut() {
+            report.source.get_or_insert(path.to_path_buf());
+        }
+        match &mut merged {
+            Some(merged) => merged.merge(init, MergeStrategy::PreferOther),
+            None => merged = Some(init),
+        }
+    }
+    Ok(merged.unwrap_or_default())
+}
+
+/// Reads the main init under `root` along with every subfolder init.
+/// Errors from all of them end up in `errored_lines`, each tagged with the file it came from.
+pub fn load_tile_init(root: std::path::PathBuf) -> Result<TileInit, AppError> {
+    load_tile_init_with(root, &ParseOptions::default())
+}
+
+pub fn load_tile_init_with(
+    root: std::path::PathBuf,
+    options: &ParseOptions,
+) -> Result<TileInit, AppError> {
+    let subfolders = collect_categories_from_subfolders_with(root.clone(), options).unwrap_or_default();
+    load_main_init_over(root, subfolders, options)
+}
+
+/// Reads the main init under `root` on top of already parsed subfolder categories
+fn load_main_init_over(
+    root: std::path::PathBuf,
+    subfolders: Vec<(TileCategory, DeserErrorReports)>,
+    options: &ParseOptions,
+) -> Result<TileInit, AppError> {
+    let mut subfolder_errors = Vec::new();
+    let additional_categories = subfolders
+        .into_iter()
+        .map(|(category, errors)| {
+            subfolder_errors.extend(errors);
+            category
+        })
+        .collect();
+    let main_init_path = root.join("init.txt");
+    let text = read_init_text(&main_init_path)
+        .map_err(|err| AppError::IOError(format!("{err:?}")))?;
+    let mut init = parse_tile_init_with(text, additional_categories, root, options)?;
+    for report in init.errored_lines.iter_mut() {
+        report.source.get_or_insert(main_init_path.clone());
+    }
+    init.errored_lines.extend(subfolder_errors);
+    Ok(init)
+}
+
+/// A [`load_tile_init_with`] running on its own thread, started by [`load_tile_init_in_background`].
+/// Dropping it without a result lets the thread finish and throws its work away.
+pub struct BackgroundLoad {
+    subfolders_done: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    subfolders_total: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    cancelled: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    result: std::sync::mpsc::Receiver<Result<TileInit, AppError>>,
+}
+
+impl BackgroundLoad {
+    /// Share of subfolders read so far, from 0 to 1. Stays at 0 while the folders are still being found.
+    pub fn progress(&self) -> f32 {
+        use std::sync::atomic::Ordering;
+        match self.subfolders_total.load(Ordering::Relaxed) {
+            0 => 0.0,
+            total => self.subfolders_done.load(Ordering::Relaxed) as f32 / total as f32,
+        }
+    }
+    /// Stops the thread before the next subfolder or the main init, no result comes after this
+    pub fn cancel(&self) {
+        self.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(std::sync::atomic::Ordering::Relaxed)
+    }
+    /// The finished load, handed out once. `None` while it's still running and after [`cancel`](Self::cancel).
+    pub fn try_take(&self) -> Option<Result<TileInit, AppError>> {
+        match self.is_cancelled() {
+            true => None,
+            false => self.result.try_recv().ok(),
+        }
+    }
+}
+
+/// Starts loading `root` like [`load_tile_init_with`] on a new thread and returns right away.
+/// Poll the returned [`BackgroundLoad`] for progress and the result.
+pub fn load_tile_init_in_background(root: std::path::PathBuf, options: ParseOptions) -> BackgroundLoad {
+    use std::sync::atomic::Ordering;
+    let (sender, result) = std::sync::mpsc::channel();
+    let load = BackgroundLoad {
+        subfolders_done: Default::default(),
+        subfolders_total: Default::default(),
+        cancelled: Default::default(),
+        result,
+    };
+    let subfolders_done = load.subfolders_done.clone();
+    let subfolders_total = load.subfolders_total.clone();
+    let cancelled = load.cancelled.clone();
+    std::thread::spawn(move || {
+        let mut folders = Vec::new();
+        collect_category_folders(&root, 1, options.max_subfolder_depth, &mut folders);
+        // the main init counts as one more step, so progress doesn't sit at 1 while it's parsed
+        subfolders_total.store(folders.len() + 1, Ordering::Relaxed);
+        let mut subfolders = Vec::new();
+        for folder in folders {
+            if cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            subfolders.extend(parse_subfolder_category(folder, &options));
+            subfolders_done.fetch_add(1, Ordering::Relaxed);
+        }
+        if cancelled.load(Ordering::Relaxed) {
+            return;
+        }
+        let init = load_main_init_over(root, subfolders, &options);
+        subfolders_done.fetch_add(1, Ordering::Relaxed);
+        _ = sender.send(init);
+    });
+    load
+}
+
+/// What [`validate_init_file`] found in a single init
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub category_count: usize,
+    pub tile_count: usize,
+    pub errored_lines: DeserErrorReports,
+}
+
+/// Parses and validates one init file without any of the gui, for checking packs in CI.
+/// Subfolder inits aren't followed, run it on each of them separately.
+pub fn validate_init_file(path: &std::path::Path) -> Result<ValidationReport, AppError> {
+    let text = read_init_text(path)
+        .map_err(|err| AppError::IOError(format!("{err:?}")))?;
+    let root = path.parent().map(|parent| parent.to_path_buf()).unwrap_or_default();
+    let options = ParseOptions {
+        validate: true,
+        ..Default::default()
+    };
+    let mut init = parse_tile_init_with(text, Vec::new(), root, &options)?;
+    for report in init.errored_lines.iter_mut() {
+        report.source.get_or_insert(path.to_path_buf());
+    }
+    Ok(ValidationReport {
+        category_count: init.categories.len(),
+        tile_count: init.tile_count(),
+        errored_lines: init.errored_lines,
+    })
+}
+
 pub fn collect_categories_from_subfolders(
     root: std::path::PathBuf,
 ) -> Result<Vec<(TileCategory, DeserErrorReports)>, DeserError> {
-    lazy_static! {
-        static ref REGEX_SPLITCOMMAS: regex::Regex =
-            regex::Regex::new(REGEXSTR_SPLITCOMMAS).unwrap();
-        static ref REGEX_CATEGORY_INDEX: regex::Regex =
-            regex::Regex::new(REGEXSTR_CATEGORY_INDEX).unwrap();
-    }
-    let x = std::fs::read_dir(root.clone())
+    collect_categories_from_subfolders_with(root, &ParseOptions::default())
+}
+
+pub fn collect_categories_from_subfolders_with(
+    root: std::path::PathBuf,
+    options: &ParseOptions,
+) -> Result<Vec<(TileCategory, DeserErrorReports)>, DeserError> {
+    let mut folders = Vec::new();
+    collect_category_folders(&root, 1, options.max_subfolder_depth, &mut folders);
+    let x = folders
+        .into_iter()
+        .filter_map(|folder| parse_subfolder_category(folder, options))
+        .collect();
+    Ok(x)
+}
+
+/// Same as [`collect_categories_from_subfolders_with`], but each subfolder is parsed on the rayon pool.
+/// Categories come back in the same order as the sequential version.
+#[cfg(feature = "parallel")]
+pub fn collect_categories_from_subfolders_parallel(
+    root: std::path::PathBuf,
+    options: &ParseOptions,
+) -> Result<Vec<(TileCategory, DeserErrorReports)>, DeserError> {
+    use rayon::prelude::*;
+    let mut folders = Vec::new();
+    collect_category_folders(&root, 1, options.max_subfolder_depth, &mut folders);
+    let x = folders
+        .into_par_iter()
+        .filter_map(|folder| parse_subfolder_category(folder, options))
+        .collect();
+    Ok(x)
+}
+
+fn parse_subfolder_category(
+    folder: std::path::PathBuf,
+    options: &ParseOptions,
+) -> Option<(TileCategory, DeserErrorReports)> {
+    let subinit = folder.join("init.txt");
+    let subcolor = folder.join("color.txt");
+
+    if let Ok(contents) = read_init_text(&subinit) {
+        let color_contents =
+            read_init_text(&subcolor).unwrap_or(String::from("255,0,0"));
+        let mut colorsplit = REGEX_SPLITCOMMAS
+            .split(color_contents.trim_start_matches(BOM))
+            .map(color_component);
+        let color = [
+            colorsplit.next().flatten().unwrap_or(255u8),
+            colorsplit.next().flatten().unwrap_or(0u8),
+            colorsplit.next().flatten().unwrap_or(0u8),
+        ];
+        let name = folder
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut errors = Vec::new();
+        let mut category = TileCategory::new_main(name, color, 0);
+        category.enabled = false;
+        category.subfolder = Some(folder.clone());
+
+        let category_found = false;
+        let mut pending_comments = Vec::new();
+        for (line_number, offset, line) in numbered_lines(&contents)
+            .filter(|(_, _, line)| !line.trim().is_empty())
+        {
+            if !category_found && line.starts_with("-[") {
+                let maybe_new_category = parse_category_header(line);
+                match maybe_new_category {
+                    Ok(newcat) => {
+                        category.name = newcat.name;
+                        category.color = newcat.color;
+                        category.comments = std::mem::take(&mut pending_comments);
+                        // saved headers carry their index as a trailer
+                        if REGEX_CATEGORY_INDEX.is_match(line) {
+                            category.index = newcat.index;
+                        }
+                    }
+                    Err(err) => errors.push(DeserErrorReport::new(line, err).at(line_number, offset)),
+                }
+            }
+            // the index trailer looks like a comment on its own line, so check it before skipping those
+            else if let Some(caps) = REGEX_CATEGORY_INDEX.captures(line) {
+                category.index = caps[1].parse().unwrap_or(1);
+            }
+            else if options.is_comment(line) {
+                pending_comments.push(line.to_string());
+            } else {
+                let maybe_new_item = parse_tile_info_with(line, true, options);
+                match maybe_new_item {
+                    Ok(mut new_item) => {
+                        new_item.comments = std::mem::take(&mut pending_comments);
+                        category.tiles.push(new_item)
+                    }
+                    Err(err) => errors.push(DeserErrorReport::new(line, err).at(line_number, offset)),
+                }
+            }
+        }
+        for report in errors.iter_mut() {
+            report.source = Some(subinit.clone());
+        }
+        return Some((category, errors,));
+    };
+    None
+}
+
+/// walks `dir` for folders holding an init.txt. a folder with its own init is a category,
+/// so its children are not searched (they would double-count tiles)
+fn collect_category_folders(
+    dir: &std::path::Path,
+    depth: usize,
+    max_depth: Option<usize>,
+    found: &mut Vec<std::path::PathBuf>,
+) {
+    let subdirs = std::fs::read_dir(dir)
         .into_iter()
         .flatten()
-        //.into_iter()
         .filter_map(|entry| entry.ok())
         .filter(|entry| { // we only want directories
             entry.file_type()
                 .map(|filetype| filetype.is_dir())
                 .unwrap_or(false)
-        })
-        .filter_map(|entry| {
-            let subinit = entry.path().join("init.txt");
-            let subcolor = entry.path().join("color.txt");
-
-            if let Ok(contents) = std::fs::read_to_string(subinit.clone()) {
-                let color_contents =
-                    std::fs::read_to_string(subcolor).unwrap_or(String::from("255,0,0"));
-                let mut colorsplit = REGEX_SPLITCOMMAS
-                    .split(color_contents.as_str())
-                    .filter_map(|substring| substring.parse::<u8>().ok());
-                let color = [
-                    colorsplit.next().unwrap_or(255u8),
-                    colorsplit.next().unwrap_or(0u8),
-                    colorsplit.next().unwrap_or(0u8),
-                ];
-                let name = entry.file_name().to_string_lossy().to_string();
-                let subfolder = root.clone().join(name.clone());
-                let mut errors = Vec::new();
-                let mut category = TileCategory::new_main(name, color, 0);
-                category.enabled = false;
-                category.subfolder = Some(subfolder);
-
-                let category_found = false;
-                for line in contents.lines().filter(|line| !line.starts_with("--") && !line.trim().is_empty()) {
-                    if let Some(caps) = REGEX_CATEGORY_INDEX.captures(line) {
-                        category.index = caps[1].parse().unwrap_or(1);
-                    }
-                    else if !category_found && line.starts_with("-[") {
-                        let maybe_new_category = parse_category_header(line);
-                        match maybe_new_category {
-                            Ok(newcat) => {
-                                category.name = newcat.name;
-                                category.color = newcat.color;
-                            }
-                            Err(err) => errors.push((line.to_string(), err)),
-                        }
-                    } else {
-                        let maybe_new_item = parse_tile_info(line, true);
-                        match maybe_new_item {
-                            Ok(new_item) => category.tiles.push(new_item),
-                            Err(err) => errors.push((line.to_string(), err)),
-                        }
-                    }
-                }
-                return Some((category, errors,));
-            };
-            None
-            //return std::fs::read_to_string(subinit).ok();
-        })
-        .collect();
-    Ok(x)
+        });
+    for entry in subdirs {
+        let path = entry.path();
+        if path.join("init.txt").is_file() {
+            found.push(path);
+        } else if max_depth.is_none_or(|max| depth < max) {
+            collect_category_folders(&path, depth + 1, max_depth, found);
+        }
+    }
 }
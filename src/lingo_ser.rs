@@ -1,5 +1,6 @@
 use crate::{
-    lingo_ser, SerErrorReports, TileCategory, TileCategoryChange, TileCell, TileInfo, TileInit,
+    lingo_de::LingoData, lingo_ser, SerErrorReports, SpecData, Specs2, TileCategory, TileCategoryChange,
+    TileInfo, TileInit,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -9,6 +10,46 @@ pub enum SerError {
     Todo,
 }
 
+/// Renders the full main init.txt as `rewrite_init` would write it, without touching disk.
+pub fn serialize_main_init(init: &TileInit) -> String {
+    let mut buffer = Vec::new();
+    write_main_init(init, &mut buffer).expect("writing to a Vec can't fail");
+    String::from_utf8(buffer).expect("serialized init is always utf8")
+}
+
+/// Streams the main init.txt one category at a time, so the whole file never sits in memory at once.
+pub fn write_main_init(init: &TileInit, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+    for category in init.categories.iter() {
+        writer.write_all(b"\n")?;
+        writer.write_all(category_init_texts(category).0.as_bytes())?;
+    }
+    for comment in init.trailing_comments.iter() {
+        writer.write_all(b"\n")?;
+        writer.write_all(comment.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Text a category contributes to the main init and to its subfolder init, respectively.
+fn category_init_texts(category: &TileCategory) -> (String, String) {
+    let cat_text_noexclude = serialize_category(category, false)
+        .into_iter()
+        .fold(String::new(), |sum, new| format!("{sum}\n{new}"));
+    let cat_text_exclude = serialize_category(category, true)
+        .into_iter()
+        .fold(String::new(), |sum, new| format!("{sum}\n{new}"));
+    match (category.enabled, category.scheduled_change.clone()) {
+        (_, TileCategoryChange::Delete) => (String::new(), String::new()),
+        (true, TileCategoryChange::None) => (cat_text_exclude, cat_text_noexclude),
+        (false, TileCategoryChange::None) => (String::new(), cat_text_noexclude),
+        (true, TileCategoryChange::MoveToSubfolder) => (cat_text_exclude, cat_text_noexclude),
+        (false, TileCategoryChange::MoveToSubfolder) => (String::new(), cat_text_noexclude),
+        (_, TileCategoryChange::MoveFromSubfolder) => (cat_text_noexclude, String::new()),
+        //(_, TileCategoryChange::Rename(_)) => todo!(),
+        // (false, TileCategoryChange::Rename(_)) => todo!(),
+    }
+}
+
 pub fn rewrite_init(
     init: &TileInit,
     _output_path: std::path::PathBuf,
@@ -30,25 +71,7 @@ pub fn rewrite_init(
             category.subfolder
         );
 
-        let cat_text_noexclude = serialize_category(&category, false)
-            .into_iter()
-            .fold(String::new(), |sum, new| format!("{sum}\n{new}"));
-        let cat_text_exclude = serialize_category(&category, true)
-            .into_iter()
-            .fold(String::new(), |sum, new| format!("{sum}\n{new}"));
-        let (cat_text_for_main, cat_text_for_sub) =
-            match (category.enabled, category.scheduled_change.clone()) {
-                (_, TileCategoryChange::Delete) => (String::new(), String::new()),
-                (true, TileCategoryChange::None) => (cat_text_exclude, cat_text_noexclude),
-                (false, TileCategoryChange::None) => (String::new(), cat_text_noexclude),
-                (true, TileCategoryChange::MoveToSubfolder) => {
-                    (cat_text_exclude, cat_text_noexclude)
-                }
-                (false, TileCategoryChange::MoveToSubfolder) => (String::new(), cat_text_noexclude),
-                (_, TileCategoryChange::MoveFromSubfolder) => (cat_text_noexclude, String::new()),
-                //(_, TileCategoryChange::Rename(_)) => todo!(),
-                // (false, TileCategoryChange::Rename(_)) => todo!(),
-            };
+        let (cat_text_for_main, cat_text_for_sub) = category_init_texts(&category);
         main_init_to_write.push('\n');
         main_init_to_write.push_str(cat_text_for_main.as_str());
 
@@ -119,6 +142,10 @@ pub fn rewrite_init(
             }
         }
     }
+    for comment in init.trailing_comments.iter() {
+        main_init_to_write.push('\n');
+        main_init_to_write.push_str(comment);
+    }
     let main_init_path = init.root.join("init.txt");
     if let Err(err) = std::fs::write(main_init_path, main_init_to_write) {
         return Err((
@@ -132,6 +159,82 @@ pub fn rewrite_init(
     Ok(errors)
 }
 
+/// Writes the pack as it would be saved into a zip at `dest_zip`: main init, subfolder inits with their colors,
+/// and every tile png taken from `src_root`. Missing pngs are returned, everything else that goes wrong is fatal.
+#[cfg(feature = "zip")]
+pub fn export_zip(
+    init: &TileInit,
+    src_root: &std::path::Path,
+    dest_zip: &std::path::Path,
+) -> Result<SerErrorReports, crate::AppError> {
+    use std::io::Write;
+    fn zip_error(err: impl std::fmt::Debug) -> crate::AppError {
+        crate::AppError::IOError(format!("{err:?}"))
+    }
+    // zip entries always use forward slashes
+    fn entry_name(folder: &std::path::Path, file: &str) -> String {
+        folder
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .chain(Some(file.to_string()))
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+    let mut errors = SerErrorReports::new();
+    let mut archive = zip::ZipWriter::new(std::fs::File::create(dest_zip).map_err(zip_error)?);
+    let options = zip::write::FileOptions::default();
+    let mut written = std::collections::HashSet::new();
+
+    archive.start_file("init.txt", options).map_err(zip_error)?;
+    write_main_init(init, &mut archive).map_err(zip_error)?;
+    for mut category in init.categories.clone().into_iter() {
+        match category.scheduled_change {
+            TileCategoryChange::Delete => continue,
+            TileCategoryChange::MoveToSubfolder => {
+                category.subfolder = Some(init.root.join(category.name.clone()))
+            }
+            TileCategoryChange::MoveFromSubfolder => category.subfolder = None,
+            TileCategoryChange::None => {}
+        }
+        let subfolder = category.subfolder.as_ref().map(|sub| {
+            sub.strip_prefix(&init.root).unwrap_or(sub.as_path()).to_path_buf()
+        });
+        if let Some(subfolder) = &subfolder {
+            let (_, cat_text_for_sub) = category_init_texts(&category);
+            let [r, g, b] = category.color;
+            archive.start_file(entry_name(subfolder, "init.txt"), options).map_err(zip_error)?;
+            archive.write_all(cat_text_for_sub.as_bytes()).map_err(zip_error)?;
+            archive.start_file(entry_name(subfolder, "color.txt"), options).map_err(zip_error)?;
+            archive.write_all(format!("{r},{g},{b}").as_bytes()).map_err(zip_error)?;
+        }
+        for tile in category.tiles.iter() {
+            let filename = format!("{}.png", tile.name);
+            let candidates = subfolder
+                .iter()
+                .map(|sub| tile.image_path(&src_root.join(sub)))
+                .chain(Some(tile.image_path(src_root)));
+            let Some(png) = candidates.filter_map(|path| std::fs::read(path).ok()).next() else {
+                errors.push(SerError::IOError {
+                    text: format!("no png for {filename}"),
+                    category: category.name.clone(),
+                });
+                continue;
+            };
+            let destinations = Some(filename.clone())
+                .into_iter()
+                .chain(subfolder.iter().map(|sub| entry_name(sub, &filename)));
+            for destination in destinations {
+                if written.insert(destination.clone()) {
+                    archive.start_file(destination, options).map_err(zip_error)?;
+                    archive.write_all(&png).map_err(zip_error)?;
+                }
+            }
+        }
+    }
+    archive.finish().map_err(zip_error)?;
+    Ok(errors)
+}
+
 pub fn backup_init_files(init: &TileInit) -> SerErrorReports {
     let mut res = SerErrorReports::new();
     let timestamp = std::time::SystemTime::now()
@@ -153,6 +256,7 @@ pub fn backup_init_files(init: &TileInit) -> SerErrorReports {
                 tiles: Vec::new(),
                 scheduled_change: TileCategoryChange::None,
                 index: 0,
+                comments: Vec::new(),
                 //scheduled_move_to_sub: false,
             },
             main_init_path,
@@ -180,17 +284,17 @@ pub fn backup_init_files(init: &TileInit) -> SerErrorReports {
     res
 }
 
+/// Header and tile lines of `category`, each preceded by the comments that were in front of it
 pub fn serialize_category(category: &TileCategory, exclude_disabled: bool) -> Vec<String> {
     let mut res = Vec::new();
+    res.extend(category.comments.iter().cloned());
     res.push(serialize_category_header(category));
-    for item in category.tiles.iter().filter_map(|tile| {
+    for tile in category.tiles.iter() {
         if !tile.active && exclude_disabled {
-            None
-        } else {
-            Some(serialize_tileinfo(tile))
+            continue;
         }
-    }) {
-        res.push(item);
+        res.extend(tile.comments.iter().cloned());
+        res.push(serialize_tile_info(tile));
     }
     res
 }
@@ -198,49 +302,86 @@ pub fn serialize_category(category: &TileCategory, exclude_disabled: bool) -> Ve
 pub fn serialize_category_header(category: &TileCategory) -> String {
     let name = category.name.clone();
     let color = aggregate_number_array(category.color.clone().into_iter().map(|num| num as i32));
-    format!(r#"-["{name}", color({color})]"#)
+    let index = category.index;
+    format!(r#"-["{name}", color({color})]--CATEGORY_INDEX:{index}"#)
 }
 
+#[deprecated(note = "use serialize_tile_info")]
 pub fn serialize_tileinfo(tile: &TileInfo) -> String {
-    let nm = tile.name.clone();
-    let sz = aggregate_number_array(tile.size.clone().into_iter());
-    let specs = aggregate_specs_array(tile.specs.clone().into_iter());
-    let specs2 = tile
-        .specs2.clone()
-        .and_then(|actual| Some(aggregate_specs_array(actual.into_iter())))
-        //.unwrap_or("0")
-        ;
-    let specs2 = match specs2 {
-        Some(actual) => format!("[{}]", actual),
-        None => "0".to_string(),
-    };
-    let tp = tile.tile_type.as_string().unwrap_or("voxelStruct");
-    let repeat = tile
-        .repeat_layers
-        .clone()
-        .and_then(|actual| Some(aggregate_number_array(actual.clone().into_iter())))
-        .unwrap_or("0".to_string());
-    let rnd = tile.random_vars.unwrap_or(1);
-    let bf_tiles = tile.buffer_tiles;
-    let pt_pos = 0;
-    let tags = aggregate_string_array(tile.tags.clone().into_iter());
-    format!(
-        r#"[#nm:"{nm}", #sz:point({sz}), #specs:[{specs}], #specs2:{specs2}, #tp:"{tp}", #repeatL:[{repeat}], #bfTiles:{bf_tiles}, #rnd:{rnd}, #ptPos:{pt_pos}, #tags:[{tags}]]"#
-    )
+    serialize_tile_info(tile)
 }
 
-fn aggregate_specs_array<'a>(specs: impl std::iter::Iterator<Item = TileCell>) -> String {
-    aggregate_number_array(specs.filter_map(|cell| cell.as_number().ok()))
+/// One init.txt line for `tile`, properties in the order the editor writes them.
+/// Empty specs and [`Specs2::Zero`] are written as the editor's `0` placeholder, a missing `specs2`, `repeatL` or `rnd` is left out.
+/// Properties tileman doesn't know go last, exactly as they were read.
+/// Known properties whose [raw value](TileInfo::raw_props) still parses to the same thing are written as they were read too.
+pub fn serialize_tile_info(tile: &TileInfo) -> String {
+    fn specs_data(specs: &SpecData) -> LingoData {
+        LingoData::Array(
+            specs
+                .iter()
+                .map(|cell| Box::new(LingoData::Number(cell.to_number())))
+                .collect(),
+        )
+    }
+    fn number_array(numbers: &[i32]) -> LingoData {
+        LingoData::Array(numbers.iter().map(|num| Box::new(LingoData::Number(*num))).collect())
+    }
+    let mut props = vec![
+        ("nm", LingoData::String(tile.name.clone())),
+        ("sz", LingoData::Point(tile.size.clone())),
+        (
+            "specs",
+            match tile.specs.is_empty() {
+                true => LingoData::Number(0),
+                false => specs_data(&tile.specs),
+            },
+        ),
+    ];
+    match &tile.specs2 {
+        Specs2::Missing => {}
+        Specs2::Zero => props.push(("specs2", LingoData::Number(0))),
+        Specs2::Layer(cells) => props.push(("specs2", specs_data(cells))),
+    }
+    props.push(("tp", LingoData::String(tile.tile_type.to_string())));
+    if let Some(repeat_layers) = &tile.repeat_layers {
+        props.push(("repeatL", number_array(&repeat_layers.0)));
+    }
+    props.push(("bfTiles", LingoData::Number(tile.buffer_tiles)));
+    if let Some(random_vars) = tile.random_vars {
+        props.push(("rnd", LingoData::Number(random_vars)));
+    }
+    props.push(("ptPos", LingoData::Number(tile.preview_pos)));
+    props.push((
+        "tags",
+        LingoData::Array(
+            tile.tags
+                .iter()
+                .map(|tag| Box::new(LingoData::String(tag.clone())))
+                .collect(),
+        ),
+    ));
+    let props = props
+        .into_iter()
+        .map(|(key, value)| {
+            let text = value.to_lingo_string();
+            match tile.raw_props.get(key) {
+                Some(raw) if LingoData::parse(raw).is_ok_and(|data| data.to_lingo_string() == text) => {
+                    format!("#{key}:{raw}")
+                }
+                _ => format!("#{key}:{text}"),
+            }
+        })
+        .chain(tile.extra_props.iter().map(|(key, raw)| format!("#{key}:{raw}")))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("[{props}]")
 }
 
 fn aggregate_number_array<'a>(numbers: impl std::iter::Iterator<Item = i32>) -> String {
     aggregate_array(numbers)
 }
 
-fn aggregate_string_array<'a>(strings: impl std::iter::Iterator<Item = String>) -> String {
-    aggregate_array(strings.map(|str| format!("\"{str}\"")))
-}
-
 pub fn aggregate_array(items: impl std::iter::Iterator<Item = impl std::fmt::Display>) -> String {
     items
         .fold(String::new(), |str, new| format!("{str},{new}"))
@@ -16,6 +16,7 @@ fn main() {
     let default_cfg = AppPersistentConfig {
         root_path: default_root,
         output_path: default_out,
+        preview_cache_size: DEFAULT_PREVIEW_CACHE_SIZE,
     };
     let cfg_path = wd.join("tileman_config.json");
     let maybe_cfg = std::fs::read_to_string(cfg_path)
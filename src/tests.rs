@@ -3,6 +3,7 @@ use crate::{
     lingo_ser,
 };
 
+#[cfg(feature = "gui")]
 #[test]
 pub fn cell_textures() {
     let celltex = crate::utl::read_cell_texture(crate::TileCell::Air).unwrap();
@@ -145,3 +146,2176 @@ pub fn parse_lingo_values() {
         ])
     )
 }
+
+fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rw_tileman_{}_{}", name, std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("could not create scratch dir");
+    dir
+}
+
+#[test]
+pub fn orphan_images() {
+    let root = scratch_dir("orphan_images");
+    let text = r#"-["Metal", color(180, 255, 255)]
+[#nm:"Small Metal", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    std::fs::write(root.join("init.txt"), text).unwrap();
+    std::fs::write(root.join("Small Metal.png"), []).unwrap();
+    std::fs::write(root.join("Leftover.png"), []).unwrap();
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), root.clone()).unwrap();
+    let orphans = crate::orphan_images(&init, &root);
+    assert_eq!(orphans, vec![root.join("Leftover.png")]);
+}
+
+#[test]
+pub fn thousands_separators() {
+    let line = r#"[#nm:"test_tile", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[10], #bfTiles:0, #rnd:1,000, #ptPos:0, #tags:[]]"#;
+    let lenient = lingo_de::parse_tile_info(line, false).unwrap();
+    assert_eq!(lenient.random_vars, Some(1000));
+    let strict = lingo_de::parse_tile_info_with(
+        line,
+        false,
+        &lingo_de::ParseOptions {
+            strict: true,
+            ..Default::default()
+        },
+    );
+    assert!(strict.is_err());
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn recent_paths_push() {
+    let mut recent = std::collections::VecDeque::new();
+    for index in 0..15 {
+        crate::app::push_recent_path(&mut recent, format!("pack{index}"));
+    }
+    assert_eq!(recent.len(), crate::app::RECENT_PATHS_CAP);
+    assert_eq!(recent.front().map(String::as_str), Some("pack14"));
+    crate::app::push_recent_path(&mut recent, String::from("pack10"));
+    assert_eq!(recent.len(), crate::app::RECENT_PATHS_CAP);
+    assert_eq!(recent.front().map(String::as_str), Some("pack10"));
+    assert_eq!(recent.iter().filter(|path| *path == "pack10").count(), 1);
+}
+
+#[test]
+pub fn validate_for_save() {
+    let good = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(good.validate_for_save(), Ok(()));
+
+    let broken = lingo_de::parse_tile_init(
+        String::from(
+            r#"-["Broken", color(0, 0, 0)]
+[#nm:"Short Specs", #sz:point(2,2), #specs:[1,1,1], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#,
+        ),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let problems = broken.validate_for_save().unwrap_err();
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("Short Specs"));
+}
+
+#[test]
+pub fn variant_for_seed() {
+    let line = r#"[#nm:"Small Stone", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStructRockType", #bfTiles:1, #rnd:4, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert_eq!(tile.variant_count(), 4);
+    assert_eq!(tile.variant_for_seed(1234), tile.variant_for_seed(1234));
+    let mut seen = [false; 4];
+    for seed in 0..64 {
+        let variant = tile.variant_for_seed(seed);
+        assert!((0..4).contains(&variant));
+        seen[variant as usize] = true;
+    }
+    assert!(seen.iter().all(|hit| *hit));
+}
+
+#[test]
+pub fn spec_data_run_length() {
+    let mut cells = vec![crate::TileCell::Air; 40];
+    cells.extend([crate::TileCell::Wall; 20]);
+    cells.push(crate::TileCell::Glass);
+    let flat = crate::SpecData::from(cells.clone());
+    let runs = flat.compressed();
+    assert_eq!(runs.len(), cells.len());
+    for index in 0..cells.len() + 2 {
+        assert_eq!(flat.spec_at(index), runs.spec_at(index));
+    }
+    assert_eq!(flat, runs);
+    assert_eq!(runs.flattened().to_vec(), cells);
+
+    let uniform = crate::SpecData::from(vec![crate::TileCell::Air; 144]);
+    assert!(uniform.compressed().approx_memory_bytes() < uniform.approx_memory_bytes());
+}
+
+#[test]
+pub fn minimal_repro() {
+    let line = r#"[#nm:"Typo Tile", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruc", #repeatL:[10], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let original_error = lingo_de::parse_tile_info(line, true).unwrap_err();
+    let repro = lingo_de::minimal_repro(line);
+    let init = lingo_de::parse_tile_init(repro, Vec::new(), Default::default()).unwrap();
+    assert_eq!(init.categories.len(), 1);
+    assert_eq!(init.errored_lines.len(), 1);
+    assert_eq!(init.errored_lines[0].error, original_error);
+}
+
+#[test]
+pub fn validate_non_positive_size() {
+    let line = r#"[#nm:"Flat Tile", #sz:point(0,2), #specs:[], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert!(matches!(
+        tile.validate(),
+        Err(lingo_de::DeserError::InvalidValue(_))
+    ));
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn diff_lines() {
+    use crate::utl::{diff_lines, DiffLine};
+    let old = "a\nb\nc\nd";
+    let new = "a\nc\nx\nd";
+    assert_eq!(
+        diff_lines(old, new),
+        vec![
+            DiffLine::Same("a".into()),
+            DiffLine::Removed("b".into()),
+            DiffLine::Same("c".into()),
+            DiffLine::Added("x".into()),
+            DiffLine::Same("d".into()),
+        ]
+    );
+    assert!(diff_lines(old, old)
+        .iter()
+        .all(|line| matches!(line, DiffLine::Same(_))));
+}
+
+#[test]
+pub fn custom_comment_prefixes() {
+    let text = "// made with some other tool\n-[\"Stone\", color(0,0,0)]\n// a comment between tiles\n[#nm:\"Small Stone\", #sz:point(1,1), #specs:[1], #specs2:0, #tp:\"voxelStructRockType\", #repeatL:[0], #bfTiles:1, #rnd:4, #ptPos:0, #tags:[\"nonSolid\"]]\n";
+    let options = lingo_de::ParseOptions {
+        comment_prefixes: vec![String::from("--"), String::from("//")],
+        ..Default::default()
+    };
+    let init =
+        lingo_de::parse_tile_init_with(text.to_string(), Vec::new(), Default::default(), &options)
+            .unwrap();
+    assert!(init.errored_lines.is_empty(), "{:?}", init.errored_lines);
+    assert_eq!(init.categories.len(), 1);
+    assert_eq!(init.categories[0].tiles.len(), 1);
+
+    let default_init =
+        lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    assert_eq!(default_init.errored_lines.len(), 2);
+
+    let (groups, errors) = lingo_de::parse_tile_info_by_category(text, &options).unwrap();
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].1.len(), 1);
+    let (_, default_errors) = lingo_de::parse_tile_info_by_category(text, &Default::default()).unwrap();
+    assert_eq!(default_errors.len(), 2);
+}
+
+#[test]
+pub fn locate_flat_index() {
+    let tile = lingo_de::parse_tile_info(
+        r#"[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#,
+        false,
+    )
+    .unwrap();
+    let mut categories = Vec::new();
+    for (index, count) in [2usize, 0, 3].into_iter().enumerate() {
+        let mut category = crate::TileCategory::new_main(format!("cat{index}"), [0, 0, 0], index);
+        category.tiles = vec![tile.clone(); count];
+        categories.push(category);
+    }
+    let init = crate::TileInit {
+        root: Default::default(),
+        categories,
+        errored_lines: Vec::new(),
+        trailing_comments: Vec::new(),
+    };
+    assert_eq!(init.locate(0), Some((0, 0)));
+    assert_eq!(init.locate(1), Some((0, 1)));
+    assert_eq!(init.locate(2), Some((2, 0)));
+    assert_eq!(init.locate(4), Some((2, 2)));
+    assert_eq!(init.locate(5), None);
+}
+
+#[test]
+pub fn lingo_data_round_trip() {
+    let values = vec![
+        LingoData::Number(-12),
+        LingoData::String(String::from("voxelStruct")),
+        LingoData::Point(vec![3, -4]),
+        LingoData::Array(Vec::new()),
+        LingoData::Array(vec![
+            Box::new(LingoData::Array(vec![
+                Box::new(LingoData::Number(1)),
+                Box::new(LingoData::Number(2)),
+            ])),
+            Box::new(LingoData::Array(Vec::new())),
+            Box::new(LingoData::String(String::from("a, b"))),
+            Box::new(LingoData::Point(vec![1, 2])),
+        ]),
+    ];
+    for value in values {
+        let text = value.to_lingo_string();
+        assert_eq!(LingoData::parse(&text).unwrap(), value, "{text}");
+    }
+    assert_eq!(
+        LingoData::Array(vec![
+            Box::new(LingoData::Number(1)),
+            Box::new(LingoData::String(String::from("x"))),
+        ])
+        .to_lingo_string(),
+        r#"[1, "x"]"#
+    );
+}
+
+#[test]
+pub fn lingo_data_variants_to_string() {
+    let cases = vec![
+        (LingoData::Number(7), "7"),
+        (LingoData::String(String::from("box")), r#""box""#),
+        (LingoData::Point(vec![2, 3]), "point(2,3)"),
+        (LingoData::Color([180, 255, 0]), "color(180,255,0)"),
+        (LingoData::Bool(true), "TRUE"),
+        (LingoData::Bool(false), "FALSE"),
+        (LingoData::Array(Vec::new()), "[]"),
+    ];
+    for (value, text) in cases {
+        assert_eq!(value.to_lingo_string(), text);
+        assert_eq!(LingoData::parse(text).unwrap(), value);
+    }
+    let mixed = LingoData::Array(vec![
+        Box::new(LingoData::String(String::from("nonSolid"))),
+        Box::new(LingoData::Number(4)),
+        Box::new(LingoData::Bool(false)),
+        Box::new(LingoData::Color([1, 2, 3])),
+        Box::new(LingoData::Array(vec![Box::new(LingoData::Number(-1))])),
+    ]);
+    let text = mixed.to_lingo_string();
+    assert_eq!(text, r#"["nonSolid", 4, FALSE, color(1,2,3), [-1]]"#);
+    assert_eq!(LingoData::parse(&text).unwrap(), mixed);
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn lru_cache() {
+    let mut cache = crate::utl::LruCache::new(2);
+    cache.insert("a", 1);
+    cache.insert("b", 2);
+    assert_eq!(cache.get(&"a"), Some(&1)); //"b" is now least recently used
+    cache.insert("c", 3);
+    assert_eq!(cache.get(&"b"), None);
+    assert_eq!(cache.get(&"c"), Some(&3));
+    cache.insert("a", 10); //replacing refreshes too, evicting nothing
+    assert_eq!(cache.get(&"c"), Some(&3));
+    assert_eq!(cache.get(&"a"), Some(&10));
+    cache.clear();
+    assert_eq!(cache.get(&"a"), None);
+}
+
+#[test]
+pub fn serialize_tile_info_round_trip() {
+    let text = std::fs::read_to_string("testfiles/init.txt").expect("could not read file");
+    let mut checked = 0;
+    for line in text.lines().filter(|line| line.starts_with('[')) {
+        let tile = lingo_de::parse_tile_info(line, false).unwrap();
+        let ser = lingo_ser::serialize_tile_info(&tile);
+        let tile2 = lingo_de::parse_tile_info(&ser, false).unwrap();
+        assert_eq!(tile, tile2, "{ser}");
+        assert_eq!(tile.preview_pos, tile2.preview_pos);
+        checked += 1;
+    }
+    assert!(checked > 0);
+
+    let line = r#"[#nm:"plain", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let ser = lingo_ser::serialize_tile_info(&lingo_de::parse_tile_info(line, false).unwrap());
+    assert_eq!(
+        ser,
+        r#"[#nm:"plain", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#
+    );
+}
+
+#[test]
+pub fn drought_tile_types() {
+    for tp in [
+        "voxelStruct",
+        "voxelStructRockType",
+        "voxelStructSandType",
+        "voxelStructRandomDisplaceVertical",
+        "voxelStructRandomDisplaceHorizontal",
+        "box",
+    ] {
+        let tile_type = crate::TileType::from_string(tp).unwrap();
+        assert_eq!(tile_type.as_string().unwrap(), tp);
+    }
+    //drought packs lean on every slope and glass code in their specs
+    let line = r#"[#nm:"Sand Slopes", #sz:point(4,2), #specs:[2,3,4,5,9,7,6,-1], #specs2:0, #tp:"voxelStructSandType", #repeatL:[1,9], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info_with(
+        line,
+        true,
+        &lingo_de::ParseOptions {
+            strict: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(tile.specs.len(), 8);
+    tile.validate().unwrap();
+}
+
+#[test]
+pub fn save_and_reload_keeps_indices() {
+    let root = scratch_dir("save_reload");
+    let tile = lingo_de::parse_tile_info(
+        r#"[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#,
+        true,
+    )
+    .unwrap();
+    let mut categories = Vec::new();
+    for (index, name) in ["Main A", "Sub B", "Main C"].into_iter().enumerate() {
+        let mut category = crate::TileCategory::new_main(name.to_string(), [10, 20, 30], index);
+        category.tiles = vec![tile.clone()];
+        categories.push(category);
+    }
+    categories[1].enabled = false;
+    categories[1].subfolder = Some(root.join("Sub B"));
+    let init = crate::TileInit {
+        root: root.clone(),
+        categories,
+        errored_lines: Vec::new(),
+        trailing_comments: Vec::new(),
+    };
+    init.save().unwrap();
+
+    let additional_categories = lingo_de::collect_categories_from_subfolders(root.clone())
+        .unwrap()
+        .into_iter()
+        .map(|(category, errors)| {
+            assert!(errors.is_empty(), "{errors:?}");
+            category
+        })
+        .collect();
+    let reloaded = lingo_de::parse_tile_init(
+        std::fs::read_to_string(root.join("init.txt")).unwrap(),
+        additional_categories,
+        root.clone(),
+    )
+    .unwrap();
+    let summary: Vec<(String, usize, usize, bool)> = reloaded
+        .categories
+        .iter()
+        .map(|cat| (cat.name.clone(), cat.index, cat.tiles.len(), cat.subfolder.is_some()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (String::from("Main A"), 0, 1, false),
+            (String::from("Sub B"), 1, 1, true),
+            (String::from("Main C"), 2, 1, false),
+        ]
+    );
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn category_text_contrast() {
+    let dark = crate::TileCategory::new_main(String::from("Stone"), [20, 20, 60], 0);
+    assert_eq!(dark.text_color_for_contrast(), egui::Color32::WHITE);
+    let light = crate::TileCategory::new_main(String::from("Metal"), [180, 255, 255], 1);
+    assert_eq!(light.text_color_for_contrast(), egui::Color32::BLACK);
+}
+
+#[test]
+pub fn negative_numbers() {
+    let line = r#"[#nm:"offset", #sz:point(2,1), #specs:[-1,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:-2, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info(line, false).unwrap();
+    assert_eq!(tile.preview_pos, -2);
+    assert_eq!(tile.specs.spec_at(0), Some(crate::TileCell::Any));
+    assert_eq!(
+        LingoData::parse("point(-1,-4)").unwrap(),
+        LingoData::Point(vec![-1, -4])
+    );
+    assert_eq!(LingoData::parse("-3").unwrap().as_number().unwrap(), -3);
+    assert_eq!(
+        LingoData::parse("[-3, 0, -12]").unwrap().as_number_array().unwrap(),
+        vec![-3, 0, -12]
+    );
+}
+
+#[test]
+pub fn lingo_data_floats() {
+    assert_eq!(LingoData::parse("0.5").unwrap(), LingoData::Float(0.5));
+    assert_eq!(LingoData::parse("-2.25").unwrap().as_float().unwrap(), -2.25);
+    assert_eq!(LingoData::parse("7").unwrap(), LingoData::Number(7));
+    assert!(matches!(
+        LingoData::parse("inf").unwrap(),
+        LingoData::InvalidOrNull(_)
+    ));
+    assert_eq!(LingoData::Float(3.0).as_number().unwrap(), 3);
+    assert!(matches!(
+        LingoData::Float(2.5).as_number(),
+        Err(lingo_de::DeserError::DataConvertFailed(_))
+    ));
+    let value = LingoData::Array(vec![
+        Box::new(LingoData::Float(1.0)),
+        Box::new(LingoData::Float(-0.75)),
+    ]);
+    assert_eq!(LingoData::parse(&value.to_lingo_string()).unwrap(), value);
+}
+
+#[test]
+pub fn validate_repeat_layers() {
+    let line = r#"[#nm:"flat layer", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1,0,1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info(line, false).unwrap();
+    assert!(matches!(
+        tile.validate(),
+        Err(lingo_de::DeserError::InvalidValue(_))
+    ));
+    let fixed = lingo_de::parse_tile_info(&line.replace("[1,0,1]", "[1,2,1]"), false).unwrap();
+    assert!(fixed.validate().is_ok());
+}
+
+#[test]
+pub fn write_init_to_buffer() {
+    let init = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let mut buffer = Vec::new();
+    init.write_to(&mut buffer).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+    assert_eq!(text, lingo_ser::serialize_main_init(&init));
+
+    let reparsed = lingo_de::parse_tile_init(text, Vec::new(), Default::default()).unwrap();
+    assert!(reparsed.errored_lines.is_empty(), "{:?}", reparsed.errored_lines);
+    assert_eq!(reparsed.categories.len(), init.categories.len());
+    for (category, reparsed_category) in init.categories.iter().zip(reparsed.categories.iter()) {
+        assert_eq!(category.name, reparsed_category.name);
+        assert_eq!(category.color, reparsed_category.color);
+        assert_eq!(category.tiles, reparsed_category.tiles);
+    }
+}
+
+#[test]
+pub fn unknown_props_survive_round_trip() {
+    let line = r#"[#nm:"custom", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #myCustomProp:5, #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[], #glowColor:point(1,2)]"#;
+    let tile = lingo_de::parse_tile_info(line, false).unwrap();
+    assert_eq!(
+        tile.extra_props,
+        vec![
+            (String::from("myCustomProp"), String::from("5")),
+            (String::from("glowColor"), String::from("point(1,2)")),
+        ]
+    );
+    let ser = lingo_ser::serialize_tile_info(&tile);
+    assert!(ser.contains("#myCustomProp:5"), "{ser}");
+    assert_eq!(lingo_de::parse_tile_info(&ser, false).unwrap(), tile);
+}
+
+#[test]
+pub fn legacy_row_sentinels() {
+    let options = lingo_de::ParseOptions {
+        legacy_row_sentinel: Some(9),
+        ..Default::default()
+    };
+    let legacy = r#"[#nm:"old", #sz:point(2,2), #specs:[1,0,9,0,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info_with(legacy, false, &options).unwrap();
+    use crate::TileCell::{Air, Glass, Wall};
+    assert_eq!(tile.specs.to_vec(), vec![Wall, Air, Air, Wall]);
+    assert_eq!(
+        lingo_de::parse_tile_info(legacy, false).unwrap().specs.len(),
+        5
+    );
+    //cell counts that already add up are left alone, even if they contain the sentinel
+    let glass = legacy.replace("[1,0,9,0,1]", "[9,9,9,9]");
+    let tile = lingo_de::parse_tile_info_with(&glass, false, &options).unwrap();
+    assert_eq!(tile.specs.to_vec(), vec![Glass; 4]);
+}
+
+#[test]
+pub fn void_specs2() {
+    assert_eq!(LingoData::parse("void").unwrap(), LingoData::Void);
+    assert_eq!(LingoData::parse("VOID").unwrap(), LingoData::Void);
+    assert_eq!(LingoData::Number(0).as_null_if_zero(), LingoData::Void);
+    assert_eq!(LingoData::Void.to_lingo_string(), "void");
+
+    let line = r#"[#nm:"voided", #sz:point(1,1), #specs:[1], #specs2:void, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let strict = lingo_de::ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let tile = lingo_de::parse_tile_info_with(line, false, &strict).unwrap();
+    assert_eq!(tile.specs2, crate::Specs2::Zero);
+    let zeroed =
+        lingo_de::parse_tile_info(&line.replace("#specs2:void", "#specs2:0"), false).unwrap();
+    assert_eq!(tile, zeroed);
+}
+
+#[test]
+pub fn load_tags_errors_with_source() {
+    let root = scratch_dir("error_sources");
+    let bad_line = r#"[#nm:"broken", #sz:point(1,1)]"#;
+    std::fs::write(
+        root.join("init.txt"),
+        format!("-[\"Main\", color(0,0,0)]\n{bad_line}\n"),
+    )
+    .unwrap();
+    std::fs::create_dir(root.join("Sub")).unwrap();
+    std::fs::write(
+        root.join("Sub").join("init.txt"),
+        format!("-[\"Sub\", color(0,0,0)]\n{bad_line}\n"),
+    )
+    .unwrap();
+
+    let init = lingo_de::load_tile_init(root.clone()).unwrap();
+    let mut sources: Vec<_> = init
+        .errored_lines
+        .iter()
+        .map(|report| {
+            assert_eq!(report.text, bad_line);
+            report.source.clone()
+        })
+        .collect();
+    sources.sort();
+    assert_eq!(
+        sources,
+        vec![
+            Some(root.join("Sub").join("init.txt")),
+            Some(root.join("init.txt")),
+        ]
+    );
+}
+
+#[test]
+pub fn error_line_numbers() {
+    let text = "-[\"Main\", color(0,0,0)]\n-- a comment\n[#nm:\"broken\", #sz:point(1,1)]\n";
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    let report = &init.errored_lines[0];
+    assert_eq!(report.line_number, Some(3));
+    assert_eq!(report.byte_offset, Some(text.find("[#nm").unwrap()));
+    assert!(report.to_string().starts_with("line 3: "), "{report}");
+
+    let tile_lines = &text[text.find("[#nm").unwrap()..];
+    let (_, errors) = lingo_de::parse_tile_info_multiple(tile_lines).unwrap();
+    assert_eq!(errors[0].line_number, Some(1));
+}
+
+#[test]
+pub fn atlas_estimate() {
+    let text = r#"-["Atlas", color(0,0,0)]
+[#nm:"layered", #sz:point(2,1), #specs:[1,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1,2,1], #bfTiles:1, #rnd:2, #ptPos:0, #tags:[]]
+[#nm:"boxy", #sz:point(3,3), #specs:[1,1,1,1,1,1,1,1,1], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    let tiles = &init.categories[0].tiles;
+    //(2 + 2 buffer) cells * 2 variants wide, (1 + 2 buffer) cells * 3 layers tall
+    assert_eq!(tiles[0].sprite_sheet_size(20), (160, 180));
+    assert_eq!(tiles[1].sprite_sheet_size(20), (60, 60));
+    assert_eq!(init.atlas_estimate(20), (160 * 180 + 60 * 60, (160, 180)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn serde_json_round_trip() {
+    let init = lingo_de::load_tile_init(
+        std::env::current_dir()
+            .expect("Could not get working directory")
+            .join("testfiles"),
+    )
+    .unwrap();
+    let json = serde_json::to_string(&init).unwrap();
+    let back: crate::TileInit = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, init);
+    //TileCategory only compares names, so check the whole thing too
+    assert_eq!(format!("{back:?}"), format!("{init:?}"));
+
+    let data = LingoData::Array(vec![
+        Box::new(LingoData::Float(0.5)),
+        Box::new(LingoData::Void),
+        Box::new(LingoData::Color([1, 2, 3])),
+    ]);
+    let back: LingoData = serde_json::from_str(&serde_json::to_string(&data).unwrap()).unwrap();
+    assert_eq!(back, data);
+}
+
+#[test]
+pub fn iter_tiles() {
+    let mut init = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let count = init.tile_count();
+    assert!(count > 0);
+    assert_eq!(init.iter_tiles().count(), count);
+    let (first_category, first_tile) = init.iter_tiles().next().unwrap();
+    assert_eq!(first_tile, &init.categories[0].tiles[0]);
+    assert_eq!(first_category.name, init.categories[0].name);
+
+    for (category_index, tile) in init.iter_tiles_mut() {
+        if category_index == 1 {
+            tile.active = false;
+        }
+    }
+    assert!(init.categories[1].tiles.iter().all(|tile| !tile.active));
+    assert!(init.categories[0].tiles.iter().all(|tile| tile.active));
+}
+
+#[test]
+pub fn isolate_and_merge_category() {
+    let mut init = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let original = init.clone();
+    let name = init.categories[1].name.clone();
+    let mut isolated = init.isolate_category(&name).unwrap();
+    assert_eq!(isolated.categories.len(), 1);
+    assert_eq!(isolated.root, init.root);
+    isolated.categories[0].tiles[0].tags.push(String::from("edited"));
+    assert!(init.isolate_category("no such category").is_none());
+
+    init.merge(isolated, crate::MergeStrategy::PreferOther);
+    assert_eq!(init.tile_count(), original.tile_count());
+    for (category, original_category) in init.categories.iter().zip(original.categories.iter()) {
+        if category.name == name {
+            assert!(category.tiles[0].tags.contains(&String::from("edited")));
+            assert_eq!(category.tiles[1..], original_category.tiles[1..]);
+        } else {
+            assert_eq!(format!("{category:?}"), format!("{original_category:?}"));
+        }
+    }
+}
+
+#[test]
+pub fn validate_while_parsing() {
+    let text = r#"-["Checked", color(0,0,0)]
+[#nm:"good", #sz:point(2,1), #specs:[1,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+[#nm:"short", #sz:point(2,3), #specs:[1,1,1,1,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let options = lingo_de::ParseOptions {
+        validate: true,
+        ..Default::default()
+    };
+    let init =
+        lingo_de::parse_tile_init_with(text.to_string(), Vec::new(), Default::default(), &options)
+            .unwrap();
+    assert_eq!(init.categories[0].tiles.len(), 1);
+    assert_eq!(init.errored_lines.len(), 1);
+    assert_eq!(init.errored_lines[0].line_number, Some(3));
+    assert!(matches!(
+        init.errored_lines[0].error,
+        lingo_de::DeserError::InvalidValue(_)
+    ));
+
+    let unchecked =
+        lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    assert_eq!(unchecked.categories[0].tiles.len(), 2);
+}
+
+#[test]
+pub fn oversized_sprites() {
+    let text = r#"-["Sizes", color(0,0,0)]
+[#nm:"small", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+[#nm:"huge", #sz:point(40,2), #specs:[], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:5, #rnd:3, #ptPos:0, #tags:[]]"#;
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    //(40 + 2 * 5) cells * 20px * 3 variants
+    assert_eq!(
+        init.oversized_sprites(2048, 20),
+        vec![(String::from("huge"), (3000, 240))]
+    );
+    assert!(init.oversized_sprites(4096, 20).is_empty());
+}
+
+#[test]
+pub fn nested_subfolders() {
+    let root = scratch_dir("nested_subfolders");
+    let line = r#"[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    for folder in ["Flat", "Flat/Shadowed", "Outer/Inner", "Deep/Er/Est"] {
+        std::fs::create_dir_all(root.join(folder)).unwrap();
+        std::fs::write(root.join(folder).join("init.txt"), line).unwrap();
+    }
+    let found = |max_subfolder_depth| {
+        let options = lingo_de::ParseOptions {
+            max_subfolder_depth,
+            ..Default::default()
+        };
+        let mut found: Vec<std::path::PathBuf> =
+            lingo_de::collect_categories_from_subfolders_with(root.clone(), &options)
+                .unwrap()
+                .into_iter()
+                .map(|(category, _)| category.subfolder.unwrap())
+                .collect();
+        found.sort();
+        found
+    };
+    assert_eq!(
+        found(None),
+        vec![root.join("Deep/Er/Est"), root.join("Flat"), root.join("Outer/Inner")]
+    );
+    assert_eq!(found(Some(2)), vec![root.join("Flat"), root.join("Outer/Inner")]);
+    assert_eq!(found(Some(1)), vec![root.join("Flat")]);
+}
+
+#[test]
+pub fn apply_palette() {
+    let text = r#"-["Stone", color(10,10,10)]
+-["Wood", color(20,20,20)]
+-["Metal", color(30,30,30)]"#;
+    let mut init =
+        lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    let unmatched = init.apply_palette(&[
+        (String::from("Stone"), [200, 100, 0]),
+        (String::from("Glass"), [0, 0, 255]),
+        (String::from("Wood"), [90, 60, 30]),
+    ]);
+    assert_eq!(unmatched, vec![String::from("Glass")]);
+    let colors: Vec<[u8; 3]> = init.categories.iter().map(|category| category.color).collect();
+    assert_eq!(colors, vec![[200, 100, 0], [90, 60, 30], [30, 30, 30]]);
+}
+
+/// the parallel parse gives exactly what the sequential one does, tiles and errors included.
+/// timings are in `cargo bench --features parallel --bench parse`
+#[cfg(feature = "parallel")]
+#[test]
+pub fn parallel_parse_matches_sequential() {
+    let root = std::env::current_dir().unwrap().join("testfiles");
+    let text = std::fs::read_to_string("testfiles/mass_deser.txt").expect("could not read file");
+    let options = lingo_de::ParseOptions::default();
+
+    let sequential =
+        lingo_de::parse_tile_init_with(text.clone(), Vec::new(), root.clone(), &options).unwrap();
+    let parallel = lingo_de::parse_tile_init_parallel(text, Vec::new(), root.clone(), &options).unwrap();
+    assert_eq!(format!("{parallel:?}"), format!("{sequential:?}"));
+
+    let sequential = lingo_de::load_tile_init(root.clone()).unwrap();
+    let parallel = lingo_de::load_tile_init_parallel(root, &options).unwrap();
+    assert_eq!(format!("{parallel:?}"), format!("{sequential:?}"));
+}
+
+#[test]
+pub fn tile_cell_numbers() {
+    for number in -100..=100 {
+        match crate::TileCell::from_number(number) {
+            Ok(cell) => {
+                assert!((-1..=7).contains(&number) || number == 9, "{number}");
+                assert_eq!(cell.to_number(), number);
+            }
+            Err(err) => {
+                assert!(matches!(err, lingo_de::DeserError::InvalidValue(_)));
+                assert!(number < -1 || number == 8 || number > 9, "{number}");
+            }
+        }
+    }
+}
+
+#[test]
+pub fn line_ending_consistency() {
+    use lingo_de::{detect_line_ending_consistency, LineEndingReport};
+    assert_eq!(
+        detect_line_ending_consistency("a\r\nb\nc\r\n"),
+        LineEndingReport::Mixed { lf: 1, crlf: 2 }
+    );
+    assert_eq!(detect_line_ending_consistency("a\nb\n"), LineEndingReport::Lf);
+    assert_eq!(detect_line_ending_consistency("a\r\nb"), LineEndingReport::Crlf);
+    assert_eq!(detect_line_ending_consistency("a"), LineEndingReport::NoLineBreaks);
+}
+
+#[test]
+pub fn unknown_tags() {
+    let text = r#"-["Tags", color(0,0,0)]
+[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["notTrashProp", "notVisble"]]
+[#nm:"b", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["notProp"]]"#;
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    let known = ["notTrashProp", "notProp", "notVisible"];
+    assert_eq!(
+        init.unknown_tags(&known),
+        vec![(String::from("a"), String::from("notVisble"))]
+    );
+}
+
+#[test]
+pub fn tile_type_strings() {
+    use crate::TileType;
+    // no wildcard, so a new variant fails to compile here. giving it the next number and bumping ARMS
+    // then fails the asserts below until ALL lists every variant, each once
+    const ARMS: usize = 6;
+    let arm = |tile_type: TileType| match tile_type {
+        TileType::VoxelStruct => 0,
+        TileType::VoxelStructRockType => 1,
+        TileType::VoxelStructDisplaceV => 2,
+        TileType::VoxelStructDisplaceH => 3,
+        TileType::VoxelStructSandType => 4,
+        TileType::Box => 5,
+    };
+    assert_eq!(TileType::ALL.len(), ARMS);
+    let mut arms: Vec<usize> = TileType::ALL.iter().map(|tile_type| arm(*tile_type)).collect();
+    arms.sort();
+    assert_eq!(arms, (0..ARMS).collect::<Vec<_>>());
+    for tile_type in TileType::ALL {
+        let text = tile_type.to_string();
+        assert_eq!(TileType::from_string(&text).unwrap(), tile_type);
+        assert_eq!(TileType::from_string(&text).unwrap().to_string(), text);
+    }
+    match TileType::from_string("voxelStruk") {
+        Err(lingo_de::DeserError::InvalidValue(message)) => assert!(message.contains("voxelStruk")),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+pub fn three_component_size() {
+    let line = r#"[#nm:"cube", #sz:point(2,2,3), #specs:[1,1,1,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert_eq!((tile.width(), tile.height(), tile.depth()), (Some(2), Some(2), Some(3)));
+    tile.validate().unwrap();
+
+    let flat = lingo_de::parse_tile_info(&line.replace("point(2,2,3)", "point(2,2)"), true).unwrap();
+    assert_eq!(flat.depth(), None);
+    let boxed = lingo_de::parse_tile_info(&line.replace(r#""voxelStruct""#, r#""box""#), true).unwrap();
+    assert!(boxed.validate().is_err());
+}
+
+#[test]
+pub fn punctuation_in_strings() {
+    let line = r#"[#nm:"Drip (big)", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["don't, really", "a&b"]]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert_eq!(tile.name, "Drip (big)");
+    assert_eq!(tile.tags, vec![String::from("don't, really"), String::from("a&b")]);
+
+    let line = r#"[#nm:"The \"Big\" One", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["say \"hi\""]]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert_eq!(tile.name, r#"The "Big" One"#);
+    assert_eq!(tile.tags, vec![String::from(r#"say "hi""#)]);
+    let reparsed =
+        lingo_de::parse_tile_info(&lingo_ser::serialize_tile_info(&tile), true).unwrap();
+    assert_eq!(reparsed.name, tile.name);
+    assert_eq!(reparsed.tags, tile.tags);
+}
+
+#[test]
+pub fn find_tiles_and_categories() {
+    let text = r#"-["First", color(0,0,0)]
+[#nm:"shared", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Second", color(0,0,0)]
+[#nm:"shared", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:2, #ptPos:0, #tags:[]]
+[#nm:"only", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let mut init =
+        lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    assert_eq!(init.find_category("Second").unwrap().tiles.len(), 2);
+    assert!(init.find_category("Third").is_none());
+    let (category, tile) = init.find_tile("shared").unwrap();
+    assert_eq!((category.name.as_str(), tile.random_vars), ("First", Some(1)));
+    assert_eq!(init.find_tile("only").unwrap().0.name, "Second");
+    assert!(init.find_tile("missing").is_none());
+
+    let (category_index, tile) = init.find_tile_mut("only").unwrap();
+    assert_eq!(category_index, 1);
+    tile.random_vars = Some(5);
+    init.find_category_mut("Second").unwrap().color = [1, 2, 3];
+    assert_eq!(init.find_tile("only").unwrap().1.random_vars, Some(5));
+    assert_eq!(init.categories[1].color, [1, 2, 3]);
+}
+
+#[test]
+pub fn heaviest_categories() {
+    let small = r#"[#nm:"small", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let big = format!(
+        r#"[#nm:"big", #sz:point(20,20), #specs:[{}], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#,
+        vec!["1"; 400].join(",")
+    );
+    let text = format!(
+        "-[\"A\", color(0,0,0)]\n{small}\n-[\"Big\", color(0,0,0)]\n{big}\n-[\"B\", color(0,0,0)]\n{small}\n{}\n-[\"C\", color(0,0,0)]\n",
+        small.replace("small", "small 2")
+    );
+    let init = lingo_de::parse_tile_init(text, Vec::new(), Default::default()).unwrap();
+    let heaviest = init.heaviest_categories(2);
+    let names: Vec<&str> = heaviest.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["Big", "B"]);
+    assert!(heaviest[0].1 > heaviest[1].1);
+    assert_eq!(init.heaviest_categories(10).len(), 4);
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn read_only_drops_edits() {
+    use crate::app::AppScheduledAction;
+    assert!(AppScheduledAction::MoveCategory(0, 1).edits_pack());
+    assert!(!AppScheduledAction::Reload.edits_pack());
+    assert!(!AppScheduledAction::PreviewChanges.edits_pack());
+    assert!(!AppScheduledAction::None.edits_pack());
+}
+
+#[test]
+pub fn duplicate_tile_names() {
+    let text = r#"-["First", color(0,0,0)]
+[#nm:"shared", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+[#nm:"unique", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Second", color(0,0,0)]
+[#nm:"shared", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:2, #ptPos:0, #tags:[]]"#;
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    let duplicates = init.find_duplicate_names();
+    assert_eq!(duplicates.len(), 1);
+    let (name, categories) = &duplicates[0];
+    assert_eq!(name, "shared");
+    let category_names: Vec<&str> = categories.iter().map(|category| category.name.as_str()).collect();
+    assert_eq!(category_names, vec!["First", "Second"]);
+}
+
+#[test]
+pub fn zero_specs() {
+    let line = r#"[#nm:"inert", #sz:point(2,1), #specs:0, #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert!(tile.specs.is_empty());
+    assert!(tile.specs2.layer().is_none());
+    tile.validate().unwrap();
+    let ser = lingo_ser::serialize_tile_info(&tile);
+    assert!(ser.contains("#specs:0,"), "{ser}");
+    assert_eq!(lingo_de::parse_tile_info(&ser, true).unwrap(), tile);
+}
+
+#[test]
+pub fn merge_strategies() {
+    use crate::MergeStrategy;
+    let base = lingo_de::parse_tile_init(
+        String::from(
+            r#"-["Shared", color(1,1,1)]
+[#nm:"same", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+[#nm:"clash", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#,
+        ),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let patch = lingo_de::parse_tile_init(
+        String::from(
+            r#"-["Shared", color(2,2,2)]
+[#nm:"same", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+[#nm:"clash", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:4, #ptPos:0, #tags:[]]
+[#nm:"new", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Patch only", color(3,3,3)]"#,
+        ),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let merged = |strategy| {
+        let mut init = base.clone();
+        init.merge(patch.clone(), strategy);
+        assert_eq!(init.categories.len(), 2);
+        assert_eq!(init.categories[1].name, "Patch only");
+        init
+    };
+    let tiles = |init: &crate::TileInit| -> Vec<(String, Option<i32>)> {
+        init.iter_tiles()
+            .map(|(_, tile)| (tile.name.clone(), tile.random_vars))
+            .collect()
+    };
+    let owned = |pairs: &[(&str, i32)]| -> Vec<(String, Option<i32>)> {
+        pairs.iter().map(|(name, rnd)| (name.to_string(), Some(*rnd))).collect()
+    };
+
+    let init = merged(MergeStrategy::PreferOther);
+    assert_eq!(init.categories[0].color, [2, 2, 2]);
+    assert_eq!(tiles(&init), owned(&[("same", 1), ("clash", 4), ("new", 1)]));
+
+    let init = merged(MergeStrategy::PreferSelf);
+    assert_eq!(init.categories[0].color, [1, 1, 1]);
+    assert_eq!(tiles(&init), owned(&[("same", 1), ("clash", 1), ("new", 1)]));
+
+    let init = merged(MergeStrategy::KeepBoth);
+    assert_eq!(init.categories[0].color, [1, 1, 1]);
+    assert_eq!(
+        tiles(&init),
+        owned(&[("same", 1), ("clash", 1), ("clash (2)", 4), ("new", 1)])
+    );
+}
+
+#[test]
+pub fn validate_init_files() {
+    let good = lingo_de::validate_init_file(std::path::Path::new("testfiles/init.txt")).unwrap();
+    assert!(good.errored_lines.is_empty(), "{:#?}", good.errored_lines);
+    assert!(good.category_count > 0);
+    assert!(good.tile_count > 0);
+
+    let path = std::path::Path::new("testfiles/broken_init.txt");
+    let broken = lingo_de::validate_init_file(path).unwrap();
+    assert_eq!((broken.category_count, broken.tile_count), (1, 1));
+    let lines: Vec<Option<usize>> = broken.errored_lines.iter().map(|report| report.line_number).collect();
+    assert_eq!(lines, vec![Some(3), Some(4)]);
+    assert!(broken.errored_lines.iter().all(|report| report.source.as_deref() == Some(path)));
+
+    assert!(lingo_de::validate_init_file(std::path::Path::new("testfiles/missing.txt")).is_err());
+}
+
+#[test]
+pub fn clamp_preview_pos() {
+    let line = r#"[#nm:"a", #sz:point(4,4), #specs:[], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:12, #tags:[]]"#;
+    let mut tile = lingo_de::parse_tile_info(line, true).unwrap();
+    tile.clamp_preview_pos();
+    assert_eq!(tile.preview_pos, 12);
+    tile.size = vec![2, 3];
+    tile.clamp_preview_pos();
+    assert_eq!(tile.preview_pos, 6);
+    tile.preview_pos = -3;
+    tile.clamp_preview_pos();
+    assert_eq!(tile.preview_pos, 0);
+}
+
+#[cfg(feature = "zip")]
+#[test]
+pub fn export_zip() {
+    let root = scratch_dir("export_zip");
+    let tile = |name: &str| {
+        lingo_de::parse_tile_info(
+            &format!(r#"[#nm:"{name}", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#),
+            true,
+        )
+        .unwrap()
+    };
+    let mut main = crate::TileCategory::new_main(String::from("Main"), [1, 2, 3], 0);
+    main.tiles = vec![tile("a"), tile("missing")];
+    let mut sub = crate::TileCategory::new_main(String::from("Sub"), [4, 5, 6], 1);
+    sub.subfolder = Some(root.join("Sub"));
+    sub.tiles = vec![tile("b")];
+    let init = crate::TileInit {
+        root: root.clone(),
+        categories: vec![main, sub],
+        errored_lines: Vec::new(),
+        trailing_comments: Vec::new(),
+    };
+    std::fs::create_dir_all(root.join("Sub")).unwrap();
+    std::fs::write(root.join("a.png"), b"png a").unwrap();
+    std::fs::write(root.join("Sub").join("b.png"), b"png b").unwrap();
+
+    let dest = root.join("pack.zip");
+    init.export_zip(&root, &dest).unwrap();
+    let errors = lingo_ser::export_zip(&init, &root, &dest).unwrap();
+    assert_eq!(errors.len(), 1);
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+    let mut names: Vec<&str> = archive.file_names().collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec!["Sub/b.png", "Sub/color.txt", "Sub/init.txt", "a.png", "b.png", "init.txt"]
+    );
+    let mut contents = String::new();
+    std::io::Read::read_to_string(&mut archive.by_name("Sub/color.txt").unwrap(), &mut contents)
+        .unwrap();
+    assert_eq!(contents, "4,5,6");
+    contents.clear();
+    std::io::Read::read_to_string(&mut archive.by_name("init.txt").unwrap(), &mut contents).unwrap();
+    assert!(contents.contains(r#"#nm:"missing""#));
+}
+
+#[test]
+pub fn empty_categories() {
+    let text = r#"-["Empty", color(0,0,0)]
+-["Full", color(0,0,0)]
+[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Also empty", color(0,0,0)]"#;
+    let mut init =
+        lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    init.validate_for_save().unwrap();
+    let problems = init.validate_for_save_with(true).unwrap_err();
+    assert_eq!(problems.len(), 2);
+    assert!(problems[0].contains("Empty"));
+
+    assert_eq!(init.remove_empty_categories(), 2);
+    assert_eq!(init.categories.len(), 1);
+    assert_eq!((init.categories[0].name.as_str(), init.categories[0].index), ("Full", 0));
+    init.validate_for_save_with(true).unwrap();
+    assert_eq!(init.remove_empty_categories(), 0);
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn tile_filter_hides_empty_categories() {
+    use crate::app::TileFilter;
+    let text = r#"-["Walls", color(0,0,0)]
+[#nm:"brick wall", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Props", color(0,0,0)]
+[#nm:"crate", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["Brick"]]"#;
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    let mut filter = TileFilter::default();
+    assert!(filter.visible_categories(&init).is_none());
+    filter.search = "BRICK".to_string();
+    let visible = filter.visible_categories(&init).unwrap();
+    assert!(visible.contains("Walls") && visible.contains("Props"));
+    filter.search_tags = false;
+    let visible = filter.visible_categories(&init).unwrap();
+    assert!(visible.contains("Walls") && !visible.contains("Props"));
+}
+
+#[test]
+pub fn array_with_trailing_junk() {
+    let data = lingo_de::LingoData::parse("[1,2,3] extra").unwrap();
+    assert_eq!(data.as_number_array().unwrap(), vec![1, 2, 3]);
+    let data = lingo_de::LingoData::parse("[1,[2,\"]\"],3]extra").unwrap();
+    assert_eq!(data.as_number_array().unwrap(), vec![1, 3]);
+    let data = lingo_de::LingoData::parse("[1,2,3 ]").unwrap();
+    assert_eq!(data.as_number_array().unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+pub fn min_game_version() {
+    let text = r#"-["Rocks", color(0,0,0)]
+[#nm:"pebble", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStructRockType", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+[#nm:"crate", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let mut init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    assert_eq!(init.used_tile_types(), vec![crate::TileType::VoxelStructRockType, crate::TileType::Box]);
+    assert_eq!(init.min_game_version(), crate::GameVersion::Vanilla);
+    init.categories[0].tiles[0].tile_type = crate::TileType::VoxelStructSandType;
+    assert_eq!(init.min_game_version(), crate::GameVersion::Drought);
+    assert_eq!(crate::TileInit::default().min_game_version(), crate::GameVersion::Vanilla);
+}
+
+#[test]
+pub fn category_color_clamping() {
+    let category = lingo_de::parse_category_header(r#"-["Loud", color(300, -5, 128)]"#).unwrap();
+    assert_eq!(category.color, [255, 0, 128]);
+    let header = lingo_ser::serialize_category_header(&category);
+    let reparsed = lingo_de::parse_category_header(&header).unwrap();
+    assert_eq!(reparsed.color, category.color);
+    assert_eq!(
+        lingo_de::LingoData::parse("color(12, 999, 0)").unwrap(),
+        lingo_de::LingoData::Color([12, 255, 0])
+    );
+}
+
+#[test]
+pub fn rename_categories_merges_collisions() {
+    let text = r#"-["Misc", color(0,0,0)]
+[#nm:"barrel", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Clutter", color(0,0,0)]
+[#nm:"crate", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Pipes", color(0,0,0)]
+[#nm:"pipe", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let mut init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    let renamed = init.rename_categories(&[
+        ("Clutter".to_string(), "Misc".to_string()),
+        ("Pipes".to_string(), "Tubes".to_string()),
+        ("Nowhere".to_string(), "Anything".to_string()),
+    ]);
+    assert_eq!(renamed, 2);
+    let names: Vec<&str> = init.categories.iter().map(|category| category.name.as_str()).collect();
+    assert_eq!(names, vec!["Misc", "Tubes"]);
+    let misc_tiles: Vec<&str> = init.categories[0].tiles.iter().map(|tile| tile.name.as_str()).collect();
+    assert_eq!(misc_tiles, vec!["barrel", "crate"]);
+}
+
+#[test]
+pub fn tile_info_summary() {
+    let line = r#"[#nm:"Big Pipe", #sz:point(2,3), #specs:[1,1,1,1,1,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["notTrashProp", "nonSolid"]]"#;
+    let mut tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert_eq!(tile.to_string(), "Big Pipe (2x3, voxelStruct, 2 tags)");
+    tile.tags.pop();
+    tile.size.clear();
+    assert_eq!(tile.to_string(), "Big Pipe (?x?, voxelStruct, 1 tag)");
+}
+
+#[test]
+pub fn category_color_ranges() {
+    let parse_color = |header: &str| lingo_de::parse_category_header(header).map(|category| category.color);
+    assert_eq!(parse_color(r#"-["Byte", color(255, 128, 0)]"#).unwrap(), [255, 128, 0]);
+    assert_eq!(parse_color(r#"-["Unit", color(1.0, 0.5, 0.0)]"#).unwrap(), [255, 128, 0]);
+    assert_eq!(parse_color(r#"-["Alpha", color(0.5, 0.2, 0.1, 0.8)]"#).unwrap(), [128, 51, 26]);
+    //whole numbers stay 0-255 even when they're all 0 or 1
+    assert_eq!(parse_color(r#"-["Dim", color(1, 1, 0)]"#).unwrap(), [1, 1, 0]);
+    assert!(matches!(
+        parse_color(r#"-["Broken", color(red, 0, 0)]"#),
+        Err(lingo_de::DeserError::InvalidValue(_))
+    ));
+    assert!(matches!(
+        parse_color(r#"-["Short", color(10, 20)]"#),
+        Err(lingo_de::DeserError::InvalidValue(_))
+    ));
+}
+
+#[test]
+pub fn float_cell_codes() {
+    let cells = lingo_de::LingoData::parse("[1.0, 2.0]").unwrap().as_tilecell_array().unwrap();
+    let codes: Vec<i32> = cells.iter().map(|cell| cell.to_number()).collect();
+    assert_eq!(codes, vec![1, 2]);
+    assert!(matches!(
+        lingo_de::LingoData::parse("[1.5]").unwrap().as_tilecell_array(),
+        Err(lingo_de::DeserError::InvalidValue(_))
+    ));
+    let line = r#"[#nm:"half", #sz:point(1,1), #specs:[1.5], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    assert!(lingo_de::parse_tile_info(line, false).is_err());
+}
+
+#[test]
+pub fn category_index_collisions() {
+    let mut init = crate::TileInit::default();
+    for (name, index) in [("Late", 5), ("First claim", 3), ("Second claim", 3), ("Early", 1)] {
+        init.categories.push(crate::TileCategory::new_main(name.to_string(), [0, 0, 0], index));
+    }
+    assert_eq!(
+        init.check_index_collisions(),
+        vec![(3, vec!["First claim".to_string(), "Second claim".to_string()])]
+    );
+    init.sort_and_normalize_categories();
+    let order: Vec<(&str, usize)> = init
+        .categories
+        .iter()
+        .map(|category| (category.name.as_str(), category.index))
+        .collect();
+    assert_eq!(order, vec![("Early", 0), ("First claim", 1), ("Second claim", 2), ("Late", 3)]);
+    assert!(init.check_index_collisions().is_empty());
+}
+
+#[test]
+pub fn category_start_index() {
+    let text = r#"-["First", color(0,0,0)]
+[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Second", color(0,0,0)]
+[#nm:"b", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let options = lingo_de::ParseOptions {
+        category_start_index: 1,
+        ..Default::default()
+    };
+    let mut init =
+        lingo_de::parse_tile_init_with(text.to_string(), Vec::new(), Default::default(), &options).unwrap();
+    let indices: Vec<usize> = init.categories.iter().map(|category| category.index).collect();
+    assert_eq!(indices, vec![1, 2]);
+    init.categories.swap(0, 1);
+    init.sort_and_normalize_categories_from(1);
+    assert_eq!(init.categories[0].name, "First");
+    assert_eq!(init.categories[0].index, 1);
+    init.sort_and_normalize_categories();
+    assert_eq!(init.categories[0].index, 0);
+}
+
+#[test]
+pub fn subfolder_category_with_other_color_keeps_tiles() {
+    let sub_line = r#"[#nm:"sub tile", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let mut subfolder_category = crate::TileCategory::new_main("Shared".to_string(), [10, 20, 30], 0);
+    subfolder_category.subfolder = Some(std::path::PathBuf::from("Shared"));
+    subfolder_category.tiles.push(lingo_de::parse_tile_info(sub_line, true).unwrap());
+    let text = r#"-["Shared", color(200,100,0)]"#;
+    let init = lingo_de::parse_tile_init(text.to_string(), vec![subfolder_category], Default::default()).unwrap();
+    assert_eq!(init.categories.len(), 1);
+    let category = &init.categories[0];
+    assert_eq!(category.color, [200, 100, 0]);
+    assert_eq!(category.subfolder, Some(std::path::PathBuf::from("Shared")));
+    assert_eq!(category.tiles.len(), 1);
+    assert_eq!(category.tiles[0].name, "sub tile");
+}
+
+#[test]
+pub fn tile_info_builder() {
+    let tile = crate::TileInfo::builder("plain")
+        .specs(vec![crate::TileCell::Wall])
+        .tile_type(crate::TileType::Box)
+        .build()
+        .unwrap();
+    assert_eq!(
+        lingo_ser::serialize_tile_info(&tile),
+        r#"[#nm:"plain", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#
+    );
+    let tile = crate::TileInfo::builder("pillar")
+        .size(1, 2)
+        .specs(vec![crate::TileCell::Wall, crate::TileCell::Wall])
+        .tag("nonSolid")
+        .build()
+        .unwrap();
+    assert!(tile.active);
+    assert_eq!(tile.repeat_layers, Some(crate::RepeatLayers(vec![1])));
+    assert_eq!(tile.tags, vec!["nonSolid".to_string()]);
+    let mismatched = crate::TileInfo::builder("pillar")
+        .size(1, 2)
+        .specs(vec![crate::TileCell::Wall])
+        .build();
+    assert!(matches!(mismatched, Err(lingo_de::DeserError::InvalidValue(_))));
+}
+
+#[test]
+pub fn raw_props_round_trip() {
+    let line = r#"[#nm:"spaced", #sz:point( 2, 1 ), #specs:[1, 1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let options = lingo_de::ParseOptions {
+        keep_raw_props: true,
+        ..Default::default()
+    };
+    let mut tile = lingo_de::parse_tile_info_with(line, true, &options).unwrap();
+    assert_eq!(tile.raw_props.get("sz").map(String::as_str), Some("point( 2, 1 )"));
+    assert_eq!(lingo_ser::serialize_tile_info(&tile), line);
+    tile.size = vec![1, 2];
+    let ser = lingo_ser::serialize_tile_info(&tile);
+    assert!(ser.contains("#sz:point(1,2)"), "{ser}");
+    assert!(ser.contains("#specs:[1, 1]"), "{ser}");
+    assert!(lingo_de::parse_tile_info(line, true).unwrap().raw_props.is_empty());
+}
+
+#[test]
+pub fn strict_missing_size() {
+    let line = r#"[#nm:"sizeless", #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let lenient = lingo_de::parse_tile_info(line, true);
+    assert!(lenient.is_err());
+    assert!(!matches!(lenient, Err(lingo_de::DeserError::MissingValue(_))));
+    let strict = lingo_de::parse_tile_info_with(
+        line,
+        true,
+        &lingo_de::ParseOptions {
+            strict: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(strict, Err(lingo_de::DeserError::MissingValue("sz".to_string())));
+}
+
+#[test]
+pub fn bom_and_crlf_inits() {
+    let clean = r#"-["Metal", color(180, 255, 255)]--CATEGORY_INDEX:2
+[#nm:"plate", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Wood", color(120, 80, 0)]--CATEGORY_INDEX:1
+[#nm:"plank", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let windows = format!("\u{feff}{}\r\n", clean.replace('\n', "\r\n"));
+    let write = |init: &crate::TileInit| {
+        let mut out = Vec::new();
+        init.write_to(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let clean_init = lingo_de::parse_tile_init(clean.to_string(), Vec::new(), Default::default()).unwrap();
+    let windows_init = lingo_de::parse_tile_init(windows, Vec::new(), Default::default()).unwrap();
+    assert!(windows_init.errored_lines.is_empty());
+    assert_eq!(windows_init.categories[0].name, "Wood");
+    assert_eq!(write(&windows_init), write(&clean_init));
+
+    let root = scratch_dir("bom_and_crlf_inits");
+    std::fs::create_dir_all(root.join("Sub")).unwrap();
+    let sub_text = clean.lines().take(2).collect::<Vec<&str>>().join("\r\n");
+    std::fs::write(root.join("Sub").join("init.txt"), format!("\u{feff}{sub_text}\r\n")).unwrap();
+    std::fs::write(root.join("Sub").join("color.txt"), "\u{feff}1,2,3\r\n").unwrap();
+    let subfolders = lingo_de::collect_categories_from_subfolders(root.clone()).unwrap();
+    let (category, errors) = &subfolders[0];
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(category.name, "Metal");
+    assert_eq!(category.index, 2);
+    assert_eq!(category.color, [180, 255, 255]);
+    assert_eq!(category.tiles.len(), 1);
+    _ = std::fs::remove_dir_all(&root);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn json_round_trip() {
+    let init = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let write = |init: &crate::TileInit| {
+        let mut out = Vec::new();
+        init.write_to(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let json = init.to_json_pretty().unwrap();
+    assert!(json.contains("\"categories\"") && json.contains("\"tile_type\""));
+    let reread = crate::TileInit::from_json(&json).unwrap();
+    assert_eq!(write(&reread), write(&init));
+    assert!(crate::TileInit::from_json("{\"root\": 1}").is_err());
+}
+
+#[cfg(feature = "watch")]
+#[test]
+pub fn watch_debounce() {
+    use crate::watch::{Debounce, DEBOUNCE};
+    let start = std::time::Instant::now();
+    let mut debounce = Debounce::default();
+    assert!(!debounce.settled(start + DEBOUNCE));
+    //three writes of one save
+    for step in 0..3 {
+        debounce.note(start + DEBOUNCE / 4 * step);
+    }
+    assert!(debounce.is_pending());
+    assert!(!debounce.settled(start + DEBOUNCE));
+    assert!(debounce.settled(start + DEBOUNCE / 2 + DEBOUNCE));
+    assert!(!debounce.is_pending());
+    assert!(!debounce.settled(start + DEBOUNCE * 4));
+}
+
+#[test]
+pub fn tileset_diff() {
+    let base = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    assert!(base.diff(&base.clone()).is_empty());
+    let mut changed = base.clone();
+    let resized = changed.categories[0].tiles[0].name.clone();
+    changed.categories[0].tiles[0].size[0] += 1;
+    let removed = changed.categories[1].tiles.remove(0).name;
+    let diff = base.diff(&changed);
+    assert_eq!(diff.len(), 2, "{diff:#?}");
+    assert_eq!(
+        diff.modified_tiles,
+        vec![crate::TileChange {
+            category: base.categories[0].name.clone(),
+            name: resized,
+            fields: vec!["size"],
+        }]
+    );
+    assert_eq!(diff.removed_tiles, vec![(base.categories[1].name.clone(), removed)]);
+}
+
+#[test]
+pub fn point_dimensions() {
+    use lingo_de::{DeserError, LingoData};
+    assert_eq!(LingoData::parse("point(3, 4)").unwrap().as_point2(), Ok((3, 4)));
+    assert!(matches!(LingoData::parse("point(3)").unwrap().as_point2(), Err(DeserError::InvalidValue(_))));
+    assert!(matches!(
+        LingoData::parse("point(1,2,3,4,5)").unwrap().as_point2(),
+        Err(DeserError::InvalidValue(_))
+    ));
+    let line = r#"[#nm:"flat", #sz:point(3), #specs:[1,1,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    assert!(matches!(lingo_de::parse_tile_info(line, true), Err(DeserError::InvalidValue(_))));
+    let line = line.replace("point(3)", "point(1,1,1,1,3)");
+    assert!(matches!(lingo_de::parse_tile_info(&line, true), Err(DeserError::InvalidValue(_))));
+}
+
+#[test]
+pub fn sort_categories_by_name() {
+    let mut init = crate::TileInit::default();
+    for (name, index) in [("pipes", 0), ("Metal", 1), ("Bricks", 2), ("metal", 3)] {
+        let mut category = crate::TileCategory::new_main(name.to_string(), [0, 0, 0], index);
+        category.tiles = ["b", "A", "c"]
+            .into_iter()
+            .map(|tile| crate::TileInfo::builder(tile).build().unwrap())
+            .collect();
+        init.categories.push(category);
+    }
+    let names = |init: &crate::TileInit| -> Vec<String> {
+        init.categories.iter().map(|category| category.name.clone()).collect()
+    };
+    let original = names(&init);
+    init.sort_categories(crate::SortKey::Name);
+    //"Metal" and "metal" tie and keep their order
+    assert_eq!(names(&init), vec!["Bricks", "Metal", "metal", "pipes"]);
+    assert_eq!(init.categories[0].index, 2);
+    init.sort_tiles_by_name();
+    let tiles: Vec<&str> = init.categories[0].tiles.iter().map(|tile| tile.name.as_str()).collect();
+    assert_eq!(tiles, vec!["A", "b", "c"]);
+    init.sort_categories(crate::SortKey::Index);
+    assert_eq!(names(&init), original);
+}
+
+#[test]
+pub fn edit_tags() {
+    let line = r#"[#nm:"tagged", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["nonSolid"]]"#;
+    let mut tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert!(tile.add_tag(" notTrashProp "));
+    assert!(!tile.add_tag("NONSOLID"));
+    assert!(!tile.add_tag("   "));
+    assert!(tile.remove_tag("nonSolid"));
+    assert!(!tile.remove_tag("nonSolid"));
+    let ser = lingo_ser::serialize_tile_info(&tile);
+    assert!(ser.ends_with(r#"#tags:["notTrashProp"]]"#), "{ser}");
+    assert_eq!(lingo_de::parse_tile_info(&ser, true).unwrap().tags, vec!["notTrashProp".to_string()]);
+}
+
+#[test]
+pub fn parse_tile_inits() {
+    let dir = scratch_dir("parse_tile_inits");
+    let first = dir.join("first.txt");
+    let second = dir.join("second.txt");
+    std::fs::write(
+        &first,
+        "-[\"Walls\", color(255, 0, 0)]\n[#nm:\"Brick\", #sz:point(1,1), #specs:[1], #specs2:0, #tp:\"voxelStruct\", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &second,
+        "-[\"Walls\", color(0, 255, 0)]\n[#nm:\"Stone\", #sz:point(1,1), #specs:[1], #specs2:0, #tp:\"voxelStruct\", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]\n-[\"Props\", color(0, 0, 255)]\nnot a tile\n",
+    )
+    .unwrap();
+    let init = lingo_de::parse_tile_inits(&[&first, &second]).expect("could not merge");
+    assert_eq!(init.categories.len(), 2);
+    assert_eq!(init.categories[0].tiles.len(), 2);
+    assert_eq!(init.categories[0].color, [0, 255, 0]);
+    assert_eq!(init.errored_lines.len(), 1);
+    assert_eq!(init.errored_lines[0].source.as_deref(), Some(second.as_path()));
+    assert!(lingo_de::parse_tile_inits::<&std::path::Path>(&[])
+        .expect("empty list")
+        .categories
+        .is_empty());
+    _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+pub fn tileset_stats() {
+    let text = std::fs::read_to_string("testfiles/init.txt").expect("could not read file");
+    let tile_lines = text.lines().filter(|line| line.trim_start().starts_with("[#nm:")).count();
+    let category_lines = text.lines().filter(|line| line.trim_start().starts_with("-[")).count();
+    let init = lingo_de::parse_tile_init(text, Vec::new(), Default::default()).unwrap();
+    let stats = init.stats();
+    assert_eq!(stats.tiles, tile_lines - stats.errored_lines);
+    assert_eq!(stats.categories, category_lines);
+    assert_eq!(stats.tiles_per_type.values().sum::<usize>(), stats.tiles);
+    assert_eq!(stats.tiles_per_type.len(), init.used_tile_types().len());
+}
+
+#[test]
+pub fn category_tiles_active() {
+    let mut init = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let category = init
+        .categories
+        .iter_mut()
+        .find(|category| category.tiles.len() > 1)
+        .expect("no category with several tiles");
+    category.subfolder = Some(category.name.clone().into());
+    category.set_tiles_active(true);
+    assert_eq!(category.tiles_active(), Some(true));
+    category.tiles[1].active = false;
+    assert_eq!(category.tiles_active(), None);
+    category.set_tiles_active(false);
+    assert_eq!(category.tiles_active(), Some(false));
+    category.tiles.clear();
+    assert_eq!(category.tiles_active(), Some(false));
+}
+
+#[test]
+pub fn specs2_zero_or_missing() {
+    let zero = r#"[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let missing = r#"[#nm:"a", #sz:point(1,1), #specs:[1], #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let layered = r#"[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:[0], #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+
+    let zero_tile = lingo_de::parse_tile_info(zero, false).unwrap();
+    let missing_tile = lingo_de::parse_tile_info(missing, false).unwrap();
+    let layered_tile = lingo_de::parse_tile_info(layered, false).unwrap();
+    assert_eq!(zero_tile.specs2, crate::Specs2::Zero);
+    assert_eq!(missing_tile.specs2, crate::Specs2::Missing);
+    assert_eq!(layered_tile.specs2.layer().map(|cells| cells.len()), Some(1));
+    assert_ne!(zero_tile, missing_tile);
+    assert_ne!(zero_tile, layered_tile);
+
+    assert_eq!(lingo_ser::serialize_tile_info(&zero_tile), zero);
+    assert_eq!(lingo_ser::serialize_tile_info(&missing_tile), missing);
+    assert_eq!(lingo_ser::serialize_tile_info(&layered_tile), layered);
+}
+
+#[test]
+pub fn fuzzy_find() {
+    let mut text = String::from("-[\"Pipes\", color(0, 0, 0)]\n");
+    for name in ["Big Drain", "Drip Big", "Dirt Pebbles Big", "Pipe Bend", "Drip Small"] {
+        let tile = crate::TileInfo::builder(name).build().unwrap();
+        text.push_str(&lingo_ser::serialize_tile_info(&tile));
+        text.push('\n');
+    }
+    let init = lingo_de::parse_tile_init(text, Vec::new(), Default::default()).unwrap();
+    let found = init.fuzzy_find("drpbg");
+    assert_eq!(found.first().map(|(tile, _)| tile.name.as_str()), Some("Drip Big"));
+    assert!(found.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    assert!(found.iter().all(|(tile, _)| tile.name != "Pipe Bend" && tile.name != "Drip Small"));
+    assert_eq!(init.fuzzy_find("").len(), 5);
+    assert!(init.fuzzy_find("zzz").is_empty());
+}
+
+#[test]
+pub fn comments_round_trip() {
+    let text = r#"
+-- walls made by someone, credits go here
+-["Walls", color(255,0,0)]--CATEGORY_INDEX:0
+[#nm:"Brick", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-- small stuff from here on
+-- keep these square
+[#nm:"Stone", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+
+-- props
+-["Props", color(0,0,255)]--CATEGORY_INDEX:1
+[#nm:"Crate", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-- end of file"#;
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    assert!(init.errored_lines.is_empty());
+    assert_eq!(init.categories[0].comments, vec!["-- walls made by someone, credits go here"]);
+    assert_eq!(init.categories[0].tiles[1].comments.len(), 2);
+    assert_eq!(init.categories[1].comments, vec!["-- props"]);
+    assert_eq!(init.trailing_comments, vec!["-- end of file"]);
+
+    let saved = lingo_ser::serialize_main_init(&init);
+    let without_blank_lines =
+        |text: &str| text.lines().filter(|line| !line.trim().is_empty()).collect::<Vec<_>>().join("\n");
+    assert_eq!(without_blank_lines(&saved), without_blank_lines(text));
+    let reparsed = lingo_de::parse_tile_init(saved, Vec::new(), Default::default()).unwrap();
+    assert_eq!(reparsed.categories[0].tiles[1].comments, init.categories[0].tiles[1].comments);
+    assert_eq!(reparsed.trailing_comments, init.trailing_comments);
+}
+
+#[test]
+pub fn background_load() {
+    let root = std::path::PathBuf::from("testfiles");
+    let load = lingo_de::load_tile_init_in_background(root.clone(), Default::default());
+    let started = std::time::Instant::now();
+    let loaded = loop {
+        if let Some(result) = load.try_take() {
+            break result.expect("could not load");
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(30), "load never finished");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    };
+    assert_eq!(load.progress(), 1.0);
+    assert_eq!(loaded, lingo_de::load_tile_init(root.clone()).unwrap());
+
+    let cancelled = lingo_de::load_tile_init_in_background(root, Default::default());
+    cancelled.cancel();
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert!(cancelled.try_take().is_none());
+}
+
+#[test]
+pub fn export_category_to_folder() {
+    let init = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let category = init
+        .categories
+        .iter()
+        .find(|category| !category.tiles.is_empty())
+        .expect("no category with tiles");
+    let root = scratch_dir("export_category");
+    let dir = root.join("exported");
+    category.export_to_folder(&dir).unwrap();
+
+    let collected = lingo_de::collect_categories_from_subfolders_with(root.clone(), &Default::default()).unwrap();
+    assert_eq!(collected.len(), 1);
+    let (reread, errors) = &collected[0];
+    assert!(errors.is_empty());
+    assert_eq!(reread.name, category.name);
+    assert_eq!(reread.color, category.color);
+    assert_eq!(reread.subfolder.as_deref(), Some(dir.as_path()));
+    assert_eq!(reread.tiles, category.tiles);
+    _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+pub fn latin1_init() {
+    let text = "-[\"Caf\u{e9}\", color(0,0,0)]\n[#nm:\"Caf\u{e9} Table\", #sz:point(1,1), #specs:[1], #specs2:0, #tp:\"voxelStruct\", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]\n";
+    // every char is below 0x100, so Latin-1 is one byte each
+    let latin1: Vec<u8> = text.chars().map(|c| c as u8).collect();
+    assert!(std::str::from_utf8(&latin1).is_err());
+
+    let init = lingo_de::parse_tile_init_bytes(&latin1, Vec::new(), Default::default()).unwrap();
+    assert!(init.errored_lines.is_empty());
+    assert_eq!(init.categories[0].name, "Caf\u{e9}");
+    assert_eq!(init.categories[0].tiles[0].name, "Caf\u{e9} Table");
+    let utf8 = lingo_de::parse_tile_init_bytes(text.as_bytes(), Vec::new(), Default::default()).unwrap();
+    assert_eq!(utf8, init);
+
+    // windows-1252 punctuation
+    assert_eq!(crate::utl::decode_init_bytes(&[0x93, b'a', 0x94, 0x80]).0, "\u{201C}a\u{201D}\u{20AC}");
+
+    let root = scratch_dir("latin1_init");
+    let sub = root.join("Caf\u{e9}");
+    std::fs::create_dir_all(&sub).unwrap();
+    std::fs::write(sub.join("init.txt"), &latin1).unwrap();
+    let collected = lingo_de::collect_categories_from_subfolders_with(root.clone(), &Default::default()).unwrap();
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].0.tiles[0].name, "Caf\u{e9} Table");
+    _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+pub fn prune_empty_categories() {
+    let text = r#"-["Empty", color(0,0,0)]
+-["One Tile", color(0,0,0)]
+[#nm:"a", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Empty Folder", color(0,0,0)]"#;
+    let mut init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    init.categories[2].subfolder = Some(std::path::PathBuf::from("Empty Folder"));
+    let mut all = init.clone();
+
+    assert_eq!(init.prune_empty_categories(), 1);
+    let names: Vec<&str> = init.categories.iter().map(|category| category.name.as_str()).collect();
+    assert_eq!(names, vec!["One Tile", "Empty Folder"]);
+    assert_eq!(init.prune_empty_categories(), 0);
+    assert_eq!(all.prune_empty_categories_with(false), 2);
+    assert_eq!(all.categories.len(), 1);
+
+    let options = lingo_de::ParseOptions {
+        prune_empty_categories: true,
+        ..Default::default()
+    };
+    let pruned = lingo_de::parse_tile_init_with(text.to_string(), Vec::new(), Default::default(), &options).unwrap();
+    assert_eq!(pruned.categories.len(), 1);
+    assert_eq!(pruned.categories[0].name, "One Tile");
+    assert_eq!(pruned.categories[0].index, 0);
+}
+
+#[test]
+pub fn repeat_layers_typed() {
+    let line = r#"[#nm:"pillar", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1, 8, 1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info(line, false).unwrap();
+    let layers = tile.repeat_layers.clone().expect("repeatL is there");
+    assert_eq!(layers.layer_count(), 3);
+    assert_eq!(layers.depth(), 10);
+    tile.validate().unwrap();
+    assert_eq!(lingo_ser::serialize_tile_info(&tile), line);
+
+    let not_numbers = line.replace("[1, 8, 1]", r#"[1, "8", 1]"#);
+    assert!(matches!(
+        lingo_de::parse_tile_info(&not_numbers, false),
+        Err(lingo_de::DeserError::InvalidValue(_))
+    ));
+    let not_a_list = line.replace("[1, 8, 1]", "10");
+    assert!(matches!(
+        lingo_de::parse_tile_info(&not_a_list, false),
+        Err(lingo_de::DeserError::InvalidValue(_))
+    ));
+
+    let missing = lingo_de::parse_tile_info(&line.replace("#repeatL:[1, 8, 1], ", ""), false).unwrap();
+    assert_eq!(missing.repeat_layers, None);
+    assert!(matches!(missing.validate(), Err(lingo_de::DeserError::InvalidValue(_))));
+    let empty = lingo_de::parse_tile_info(&line.replace("[1, 8, 1]", "[]"), false).unwrap();
+    assert!(matches!(empty.validate(), Err(lingo_de::DeserError::InvalidValue(_))));
+    // boxes don't use repeatL at all
+    let boxed = lingo_de::parse_tile_info(
+        &line.replace("#repeatL:[1, 8, 1], ", "").replace("voxelStruct", "box"),
+        false,
+    )
+    .unwrap();
+    boxed.validate().unwrap();
+}
+
+#[test]
+pub fn copy_paste_tile() {
+    let mut init = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    let copied_tile = init.categories[0].tiles[0].clone();
+    let line = lingo_ser::serialize_tile_info(&copied_tile);
+
+    let first = init.categories[0].paste_tile(&line).unwrap();
+    let second = init.categories[0].paste_tile(&line).unwrap();
+    let tiles = &init.categories[0].tiles;
+    assert_eq!(tiles[first].name, format!("{} (2)", copied_tile.name));
+    assert_eq!(tiles[second].name, format!("{} (3)", copied_tile.name));
+    let mut renamed_back = tiles[first].clone();
+    renamed_back.name = copied_tile.name.clone();
+    assert_eq!(renamed_back, copied_tile);
+
+    let other = init.categories.len() - 1;
+    init.categories[other].tiles.retain(|tile| tile.name != copied_tile.name);
+    let pasted = init.categories[other].paste_tile(&line).unwrap();
+    assert_eq!(init.categories[other].tiles[pasted].name, copied_tile.name);
+    assert!(init.categories[0].paste_tile("not a tile").is_err());
+}
+
+#[test]
+pub fn tile_csv() {
+    let text = r#"-["Walls, Big", color(0,0,0)]
+[#nm:"Brick, Red", #sz:point(2,3), #specs:[1,1,1,1,1,1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["nonSolid", "say \"hi\""]]
+[#nm:"Plain", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"box", #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    let csv = init.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "category,name,type,width,height,tags,active");
+    assert_eq!(
+        lines[1],
+        r#""Walls, Big","Brick, Red",voxelStruct,2,3,"nonSolid;say ""hi""",true"#
+    );
+    assert_eq!(lines[2], r#""Walls, Big",Plain,box,1,1,,true"#);
+    assert_eq!(lines.len(), 3);
+}
+
+#[test]
+pub fn tile_info_by_category() {
+    let tile = |name: &str| {
+        format!(r#"[#nm:"{name}", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#)
+    };
+    let text = [
+        tile("loose"),
+        String::from("-- a comment"),
+        String::from(r#"-["First", color(0,0,0)]--CATEGORY_INDEX:4"#),
+        tile("a"),
+        String::from(r#"-["Second", color(0,0,0)]"#),
+        String::from("--CATEGORY_INDEX:7"),
+        tile("b"),
+        String::from("-5, not a tile"),
+        tile("c"),
+    ]
+    .join("\n");
+
+    let (groups, errors) = lingo_de::parse_tile_info_by_category(&text, &Default::default()).unwrap();
+    assert_eq!(groups.len(), 3);
+    assert!(groups[0].0.is_none());
+    assert_eq!(groups[0].1[0].name, "loose");
+    let first = groups[1].0.as_ref().unwrap();
+    assert_eq!((first.name.as_str(), first.index), ("First", 4));
+    let second = groups[2].0.as_ref().unwrap();
+    assert_eq!((second.name.as_str(), second.index), ("Second", 7));
+    let names: Vec<&str> = groups[2].1.iter().map(|tile| tile.name.as_str()).collect();
+    assert_eq!(names, vec!["b", "c"]);
+    // a line starting with `-` that isn't a header or comment is reported, not dropped
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line_number, Some(8));
+
+    let (tiles, flat_errors) = lingo_de::parse_tile_info_multiple(&text).unwrap();
+    assert_eq!(tiles.len(), 4);
+    assert_eq!(flat_errors.len(), 1);
+}
+
+#[test]
+pub fn preview_thumbnail() {
+    use crate::{TileCell, Thumbnail};
+    let wall = [0, 0, 0, 255];
+    let air = [255, 255, 255, 255];
+    // specs go up-then-left from the bottom right, so the last cell is the top left
+    let mut tile = crate::TileInfo::builder("pillar")
+        .size(1, 2)
+        .specs(vec![TileCell::Air, TileCell::Wall])
+        .build()
+        .unwrap();
+    let thumbnail = tile.preview_thumbnail(false);
+    assert_eq!((thumbnail.width, thumbnail.height), (1, 2));
+    assert_eq!(thumbnail.pixel(0, 0), Some(wall));
+    assert_eq!(thumbnail.pixel(0, 1), Some(air));
+    assert!(!thumbnail.mismatched);
+    assert_eq!(tile.preview_thumbnail(true), Thumbnail::default());
+
+    // too few cells leave the uncovered ones in the error pattern
+    tile.specs = vec![TileCell::Air].into();
+    let thumbnail = tile.preview_thumbnail(false);
+    assert!(thumbnail.mismatched);
+    assert_eq!(thumbnail.pixel(0, 0), Some(Thumbnail::ERROR_COLORS[0]));
+    assert_eq!(thumbnail.pixel(0, 1), Some(air));
+
+    // too many add error rows below the declared size
+    tile.specs = vec![TileCell::Air; 3].into();
+    let thumbnail = tile.preview_thumbnail(false);
+    assert!(thumbnail.mismatched);
+    assert_eq!(thumbnail.height, 3);
+    assert_eq!(thumbnail.pixel(0, 2), Some(Thumbnail::ERROR_COLORS[0]));
+
+    tile.size = vec![0, -4];
+    let thumbnail = tile.preview_thumbnail(false);
+    assert_eq!((thumbnail.width, thumbnail.height), (1, 1));
+    assert!(thumbnail.mismatched);
+}
+
+#[test]
+pub fn array_keeps_unparseable_elements() {
+    use crate::TileCell;
+    let value = LingoData::parse("[1, oops, 3]").unwrap();
+    let LingoData::Array(items) = &value else {
+        panic!("{value:?} isn't an array");
+    };
+    assert_eq!(items.len(), 3);
+    assert_eq!(*items[1], LingoData::InvalidOrNull(String::from("oops")));
+
+    // a bad cell keeps its place instead of shifting the rest of the grid
+    let cells = value.as_tilecell_array().unwrap();
+    assert_eq!(cells, vec![TileCell::Wall, TileCell::Any, TileCell::SlopeBottomRight]);
+    assert!(matches!(
+        value.as_tilecell_array_with(true),
+        Err(lingo_de::DeserError::InvalidValue(_))
+    ));
+
+    let line = r#"[#nm:"gap", #sz:point(1,3), #specs:[1, oops, 3], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert_eq!(tile.specs.len(), 3);
+    let strict = lingo_de::ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    assert!(lingo_de::parse_tile_info_with(line, true, &strict).is_err());
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn tile_list_navigation() {
+    use crate::app::{step_index, TileFilter};
+    use crate::SortKey;
+    let tile = |name: &str| {
+        format!(r#"[#nm:"{name}", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#)
+    };
+    let text = [
+        String::from(r#"-["Walls", color(0,0,0)]"#),
+        tile("wall b"),
+        tile("wall a"),
+        String::from(r#"-["Props", color(0,0,0)]"#),
+        tile("crate"),
+    ]
+    .join("\n");
+    let init = lingo_de::parse_tile_init(text, Vec::new(), Default::default()).unwrap();
+    let mut filter = TileFilter::default();
+    assert_eq!(filter.visible_tiles(&init), vec![(0, 0), (0, 1), (1, 0)]);
+    filter.sort_key = SortKey::Name;
+    assert_eq!(filter.visible_tiles(&init), vec![(1, 0), (0, 1), (0, 0)]);
+    filter.search = String::from("wall");
+    assert_eq!(filter.visible_tiles(&init), vec![(0, 1), (0, 0)]);
+
+    assert_eq!(step_index(None, 3, true), Some(0));
+    assert_eq!(step_index(None, 3, false), Some(2));
+    assert_eq!(step_index(Some(1), 3, true), Some(2));
+    assert_eq!(step_index(Some(2), 3, true), Some(2));
+    assert_eq!(step_index(Some(0), 3, false), Some(0));
+    assert_eq!(step_index(Some(5), 3, false), Some(2));
+    assert_eq!(step_index(Some(0), 0, true), None);
+}
+
+#[test]
+pub fn inline_colors() {
+    assert_eq!(LingoData::parse("color(255, 128, 0)").unwrap().as_color().unwrap(), [255, 128, 0]);
+    // same channel rules as category headers: 0-1 with a decimal point, clamped otherwise
+    assert_eq!(LingoData::parse("color(1.0, 0.5, 0.0)").unwrap().as_color().unwrap(), [255, 128, 0]);
+    assert_eq!(LingoData::parse("color(1, 1, 0)").unwrap().as_color().unwrap(), [1, 1, 0]);
+    assert_eq!(LingoData::parse("color(0.5, 0.2, 0.1, 0.8)").unwrap().as_color().unwrap(), [128, 51, 26]);
+    assert_eq!(LingoData::parse("color(300, -5, 0)").unwrap().as_color().unwrap(), [255, 0, 0]);
+    assert!(matches!(
+        LingoData::parse("color(red, 0, 0)").unwrap(),
+        LingoData::InvalidOrNull(_)
+    ));
+    assert!(LingoData::Number(3).as_color().is_err());
+
+    let line = r#"[#nm:"lamp", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[], #tint:color(0.0, 1.0, 0.5)]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    let (key, value) = &tile.extra_props[0];
+    assert_eq!(key, "tint");
+    assert_eq!(LingoData::parse(value).unwrap().as_color().unwrap(), [0, 255, 128]);
+    assert_eq!(lingo_ser::serialize_tile_info(&tile), line);
+}
+
+#[test]
+pub fn rename_category() {
+    use crate::AppError;
+    let root = scratch_dir("rename_category");
+    let mut main = crate::TileCategory::new_main(String::from("Main"), [1, 2, 3], 0);
+    main.tiles = vec![crate::TileInfo::builder("a").build().unwrap()];
+    let mut sub = crate::TileCategory::new_main(String::from("Sub"), [4, 5, 6], 1);
+    sub.subfolder = Some(root.join("Sub"));
+    std::fs::create_dir_all(root.join("Sub")).unwrap();
+    std::fs::create_dir_all(root.join("Taken")).unwrap();
+    let mut init = crate::TileInit {
+        root: root.clone(),
+        categories: vec![main, sub],
+        errored_lines: Vec::new(),
+        trailing_comments: Vec::new(),
+    };
+
+    init.rename_category("Main", "Props").unwrap();
+    assert_eq!(init.categories[0].name, "Props");
+    assert_eq!(init.categories[0].tiles.len(), 1);
+
+    // an existing name is refused instead of merged
+    assert!(matches!(
+        init.rename_category("Props", "Sub"),
+        Err(AppError::CategoryNameTaken(name)) if name == "Sub"
+    ));
+    assert_eq!(init.categories.len(), 2);
+    assert!(matches!(
+        init.rename_category("Nope", "Whatever"),
+        Err(AppError::MissingCategory(_))
+    ));
+
+    // the folder is only renamed when asked, and never over an existing one
+    init.rename_category("Sub", "Renamed").unwrap();
+    assert_eq!(init.categories[1].subfolder, Some(root.join("Sub")));
+    assert!(matches!(
+        init.rename_category_with("Renamed", "Taken", true),
+        Err(AppError::CategoryNameTaken(_))
+    ));
+    assert_eq!(init.categories[1].name, "Renamed");
+    init.rename_category_with("Renamed", "Moved", true).unwrap();
+    assert_eq!(init.categories[1].subfolder, Some(root.join("Moved")));
+    assert!(root.join("Moved").is_dir() && !root.join("Sub").exists());
+}
+
+#[test]
+pub fn move_tile() {
+    use crate::AppError;
+    let text = r#"-["Walls", color(0,0,0)]
+[#nm:"brick wall", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+[#nm:"crate", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Props", color(0,0,0)]
+[#nm:"barrel", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let mut init = lingo_de::parse_tile_init(text.to_string(), Vec::new(), Default::default()).unwrap();
+    init.move_tile("crate", "Walls", "Props").unwrap();
+    let count = |init: &crate::TileInit, name: &str| init.find_category(name).unwrap().tiles.len();
+    assert_eq!(count(&init, "Walls"), 1);
+    assert_eq!(count(&init, "Props"), 2);
+    assert_eq!(init.find_category("Props").unwrap().tiles[1].name, "crate");
+
+    assert!(matches!(
+        init.move_tile("crate", "Walls", "Props"),
+        Err(AppError::MissingTile(name)) if name == "crate"
+    ));
+    assert!(matches!(
+        init.move_tile("barrel", "Props", "Nowhere"),
+        Err(AppError::MissingCategory(name)) if name == "Nowhere"
+    ));
+    assert!(matches!(
+        init.move_tile("barrel", "Nowhere", "Props"),
+        Err(AppError::MissingCategory(_))
+    ));
+    assert_eq!(count(&init, "Props"), 2);
+
+    // a name the destination already has would be a duplicate, nothing moves
+    init.find_category_mut("Walls").unwrap().tiles[0].name = String::from("barrel");
+    assert!(matches!(
+        init.move_tile("barrel", "Walls", "Props"),
+        Err(AppError::TileNameTaken(name)) if name == "barrel"
+    ));
+    assert_eq!((count(&init, "Walls"), count(&init, "Props")), (1, 2));
+
+    // its own category is a no-op, not a move to the end
+    init.move_tile("barrel", "Props", "Props").unwrap();
+    assert_eq!(init.find_category("Props").unwrap().tiles[0].name, "barrel");
+}
+
+#[test]
+pub fn tags_trimmed_and_deduplicated() {
+    let line = r#"[#nm:"tagged", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:["foo", " foo ", "Bar", "bar"]]"#;
+    let tile = lingo_de::parse_tile_info(line, true).unwrap();
+    assert_eq!(tile.tags, vec!["foo", "Bar"]);
+    assert!(lingo_ser::serialize_tile_info(&tile).ends_with(r#"#tags:["foo", "Bar"]]"#));
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn app_with_path() {
+    let root = scratch_dir("app_with_path");
+    std::fs::write(
+        root.join("init.txt"),
+        r#"-["Walls", color(0,0,0)]
+[#nm:"brick wall", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]
+-["Props", color(0,0,0)]
+[#nm:"crate", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#,
+    )
+    .unwrap();
+    let app = crate::app::TilemanApp::with_path_and_storage(None, &root).unwrap();
+    assert_eq!(app.init().unwrap().categories.len(), 2);
+    assert!(!app.read_only());
+
+    assert!(crate::app::TilemanApp::with_path_and_storage(None, root.join("missing")).is_err());
+}
+
+#[cfg(feature = "watch")]
+#[test]
+pub fn saving_clears_unsaved_changes() {
+    let root = scratch_dir("saving_clears_unsaved_changes");
+    std::fs::write(
+        root.join("init.txt"),
+        r#"-["Walls", color(0,0,0)]
+[#nm:"brick wall", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#,
+    )
+    .unwrap();
+    let mut app = crate::app::TilemanApp::with_path_and_storage(None, &root).unwrap();
+    assert!(!app.has_unsaved_changes());
+    let init = app.init_mut().unwrap();
+    init.categories[0].tiles[0].add_tag("nonSolid");
+    init.save().unwrap();
+    assert!(app.has_unsaved_changes());
+    // what the save button schedules once writing worked
+    app.mark_saved();
+    assert!(!app.has_unsaved_changes());
+}
+
+#[cfg(feature = "gui")]
+#[test]
+pub fn saved_changes_are_not_pending() {
+    use crate::utl::DiffLine;
+    let root = scratch_dir("saved_changes_are_not_pending");
+    std::fs::write(
+        root.join("init.txt"),
+        r#"-["Walls", color(0,0,0)]
+[#nm:"brick wall", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#,
+    )
+    .unwrap();
+    let mut app = crate::app::TilemanApp::with_path_and_storage(None, &root).unwrap();
+    let is_pending = |line: &DiffLine| !matches!(line, DiffLine::Same(_));
+    let init = app.init_mut().unwrap();
+    init.categories[0].tiles[0].add_tag("nonSolid");
+    assert!(app.pending_changes().unwrap().iter().any(is_pending));
+    app.init().unwrap().save().unwrap();
+    app.mark_saved();
+    assert!(!app.pending_changes().unwrap().iter().any(is_pending));
+}
+
+#[test]
+pub fn merge_and_remove_empty_keep_start_index() {
+    let tile = r#"[#nm:"TILE", #sz:point(1,1), #specs:[1], #specs2:0, #tp:"voxelStruct", #repeatL:[1], #bfTiles:0, #rnd:1, #ptPos:0, #tags:[]]"#;
+    let options = lingo_de::ParseOptions {
+        category_start_index: 1,
+        ..Default::default()
+    };
+    let parse = |text: String| {
+        lingo_de::parse_tile_init_with(text, Vec::new(), Default::default(), &options).unwrap()
+    };
+    let indices = |init: &crate::TileInit| -> Vec<usize> {
+        init.categories.iter().map(|category| category.index).collect()
+    };
+    let mut init = parse(format!(
+        "-[\"A\", color(0,0,0)]\n{}\n-[\"B\", color(0,0,0)]\n{}",
+        tile.replace("TILE", "a"),
+        tile.replace("TILE", "b")
+    ));
+    let other = parse(format!("-[\"C\", color(0,0,0)]\n{}", tile.replace("TILE", "c")));
+    init.merge(other, crate::MergeStrategy::PreferSelf);
+    assert_eq!(indices(&init), vec![1, 2, 3]);
+    assert!(init.validate_for_save().is_ok());
+
+    init.categories[1].tiles.clear();
+    assert_eq!(init.remove_empty_categories(), 1);
+    assert_eq!(indices(&init), vec![1, 2]);
+    assert_eq!(init.categories[1].name, "C");
+}
+
+#[test]
+pub fn main_init_category_keeps_tiles_when_toggled() {
+    let mut init = lingo_de::parse_tile_init(
+        std::fs::read_to_string("testfiles/init.txt").expect("could not read file"),
+        Vec::new(),
+        Default::default(),
+    )
+    .unwrap();
+    assert!(init.categories[0].subfolder.is_none());
+    let before = lingo_ser::serialize_main_init(&init);
+    init.categories[0].set_tiles_active(false);
+    assert_eq!(init.categories[0].tiles_active(), Some(true));
+    assert_eq!(lingo_ser::serialize_main_init(&init), before);
+}
+
+#[test]
+pub fn backslashes_round_trip() {
+    let mut tile = crate::TileInfo::builder(r"dir\").build().unwrap();
+    tile.tags = vec![String::from("dir\\"), String::from("x"), String::from(r#"a\"b"#)];
+    let line = lingo_ser::serialize_tile_info(&tile);
+    assert!(line.contains(r#"#tags:["dir\\", "x", "a\\\"b"]"#), "{line}");
+    let back = lingo_de::parse_tile_info(&line, true).unwrap();
+    assert_eq!(back.name, tile.name);
+    assert_eq!(back.tags, tile.tags);
+
+    // a lone backslash in a hand-written init isn't an escape and stays
+    let data = LingoData::parse(r#""a\b""#).unwrap();
+    assert_eq!(data, LingoData::String(String::from(r"a\b")));
+    assert_eq!(LingoData::parse(&data.to_lingo_string()).unwrap(), data);
+}
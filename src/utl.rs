@@ -1,14 +1,79 @@
-use crate::{app::AppError, PrimitiveColor, TileCell};
+#[cfg(feature = "gui")]
+use crate::{AppError, PrimitiveColor, TileCell};
 
 pub fn indices<'a, T>(vec: &'a Vec<T>) -> impl Iterator<Item = usize> {
     0..vec.len()
 }
 
+/// Alphabetical, ignoring case
+pub fn compare_names(name1: &str, name2: &str) -> std::cmp::Ordering {
+    name1.to_lowercase().cmp(&name2.to_lowercase())
+}
+
+/// What Windows-1252 puts at bytes 0x80 to 0x9F, the rest of the byte range is the same as Latin-1.
+/// The five bytes it leaves undefined become the control characters of the same value, like Latin-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// Text of an init or color file: UTF-8 when the bytes are valid UTF-8, Windows-1252 otherwise,
+/// which old tilesets were saved in and which reads Latin-1 text the same. Also names the encoding used.
+pub fn decode_init_bytes(bytes: &[u8]) -> (String, &'static str) {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), "UTF-8"),
+        Err(_) => (
+            bytes
+                .iter()
+                .map(|byte| match byte {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+                    _ => *byte as char,
+                })
+                .collect(),
+            "Windows-1252",
+        ),
+    }
+}
+
+/// How well `query` matches `candidate` as a subsequence ignoring case, like fzf, so "drpbg" finds "Drip Big".
+/// None when some query character isn't there in order. Runs and word starts score higher, skipped characters cost a little.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    fn fold(c: char) -> char {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous_match: Option<usize> = None;
+    for query_char in query.chars().filter(|c| !c.is_whitespace()).map(fold) {
+        let found = (next..candidate.len()).find(|index| fold(candidate[*index]) == query_char)?;
+        score += 16;
+        let word_start = found == 0
+            || !candidate[found - 1].is_alphanumeric()
+            || (candidate[found].is_uppercase() && candidate[found - 1].is_lowercase());
+        if word_start {
+            score += 8;
+        }
+        score -= match previous_match {
+            Some(previous) if previous + 1 == found => -12,
+            Some(previous) => (found - previous - 1) as i64,
+            None => found as i64,
+        };
+        previous_match = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(feature = "gui")]
 pub fn name_matches_search(item: &String, search_selection: &String) -> bool {
     item.to_lowercase()
         .contains(search_selection.as_str().to_lowercase().as_str())
 }
 
+#[cfg(feature = "gui")]
 pub fn read_cell_texture(cell: TileCell) -> Result<multiarray::Array2D<PrimitiveColor>, AppError> {
     let path = format!("{cell:?}.png");
     let mut res = multiarray::Array2D::new(
@@ -43,3 +108,104 @@ pub fn read_cell_texture(cell: TileCell) -> Result<multiarray::Array2D<Primitive
     }
     Ok(res)
 }
+
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Line-by-line diff turning `old` into `new` (longest common subsequence)
+#[cfg(feature = "gui")]
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    //trim shared ends so the table only covers the edited middle
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    //lcs[i][j] = common subsequence length of old_mid[i..] and new_mid[j..]
+    let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut res: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::Same(line.to_string()))
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() && j < new_mid.len() {
+        if old_mid[i] == new_mid[j] {
+            res.push(DiffLine::Same(old_mid[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            res.push(DiffLine::Removed(old_mid[i].to_string()));
+            i += 1;
+        } else {
+            res.push(DiffLine::Added(new_mid[j].to_string()));
+            j += 1;
+        }
+    }
+    res.extend(old_mid[i..].iter().map(|line| DiffLine::Removed(line.to_string())));
+    res.extend(new_mid[j..].iter().map(|line| DiffLine::Added(line.to_string())));
+    res.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::Same(line.to_string())),
+    );
+    res
+}
+
+/// Small least-recently-used map. Linear lookups, meant for a few dozen entries at most.
+#[cfg(feature = "gui")]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    entries: std::collections::VecDeque<(K, V)>, //most recently used first
+}
+
+#[cfg(feature = "gui")]
+impl<K: PartialEq, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: std::collections::VecDeque::new(),
+        }
+    }
+    /// Looks up `key` and marks it as the most recently used
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let position = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(position)?;
+        self.entries.push_front(entry);
+        self.entries.front().map(|(_, v)| v)
+    }
+    /// Inserts or replaces `key`, evicting the least recently used entries past capacity
+    pub fn insert(&mut self, key: K, value: V) {
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.push_front((key, value));
+        self.entries.truncate(self.capacity);
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Changes closer together than this count as one, editors often save a file in several writes
+pub const DEBOUNCE: Duration = Duration::from_millis(400);
+
+/// Collapses a burst of changes into one, reported once nothing happened for [`DEBOUNCE`]
+#[derive(Debug, Default)]
+pub struct Debounce {
+    last_change: Option<Instant>,
+}
+
+impl Debounce {
+    pub fn note(&mut self, at: Instant) {
+        self.last_change = Some(self.last_change.map_or(at, |last| last.max(at)));
+    }
+    pub fn is_pending(&self) -> bool {
+        self.last_change.is_some()
+    }
+    /// True once per burst, after it has been quiet long enough
+    pub fn settled(&mut self, now: Instant) -> bool {
+        match self.last_change {
+            Some(last) if now.saturating_duration_since(last) >= DEBOUNCE => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Watches a pack root and everything below it. Dropping it stops watching.
+pub struct PackWatcher {
+    _watcher: notify::RecommendedWatcher,
+    changes: mpsc::Receiver<Instant>,
+    debounce: Debounce,
+}
+
+impl PackWatcher {
+    /// `ctx` gets a repaint whenever something changes, so the app notices without user input
+    pub fn new(root: &std::path::Path, ctx: egui::Context) -> notify::Result<Self> {
+        use notify::Watcher;
+        let (sender, changes) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if !event.kind.is_access() => {
+                    _ = sender.send(Instant::now());
+                    ctx.request_repaint();
+                }
+                Ok(_) => {}
+                Err(err) => log::warn!("file watcher error: {err}"),
+            })?;
+        watcher.watch(root, notify::RecursiveMode::Recursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            changes,
+            debounce: Debounce::default(),
+        })
+    }
+    /// True once after a burst of changes has settled
+    pub fn settled_change(&mut self, now: Instant) -> bool {
+        while let Ok(at) = self.changes.try_recv() {
+            self.debounce.note(at);
+        }
+        self.debounce.settled(now)
+    }
+    pub fn is_pending(&self) -> bool {
+        self.debounce.is_pending()
+    }
+}